@@ -1,44 +1,355 @@
+mod auth;
+mod cache;
 mod codeowners;
+mod graphql;
+mod rate_limit;
 
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{HashMap, HashSet},
+    time::SystemTime,
+};
 
-pub use codeowners::CodeOwners;
+pub use codeowners::{
+    ignored_codeowners_warnings, Assignment, CodeOwners, CodeOwnersFile, Level, Owner,
+};
+pub use graphql::OrganisationRepos;
+pub use rate_limit::is_rate_limited;
 
+use crate::GitHubOptions;
 use eyre::{eyre, WrapErr};
+use itertools::Itertools;
 use lazy_static::lazy_static;
 use regex::Regex;
 use serde::de::DeserializeOwned;
-
-/// A GitHub API client that optionally authenticates requests.
+use tokio::sync::Mutex;
+
+/// A GitHub API client that optionally authenticates requests, either with a
+/// personal access token or as a GitHub App installation.
+///
+/// The API and raw content base URLs are configurable so that the tool can be
+/// run against GitHub Enterprise or a local stand-in server.
+///
+/// Requests that fail due to rate limiting or transient server errors are
+/// retried with backoff, and responses may be cached on disk between runs.
 pub struct Client {
-    github_api_token: Option<String>,
+    auth: auth::Auth,
+    api_url: String,
+    raw_url: String,
+    http: reqwest::Client,
+    cache: Option<cache::Cache>,
+    graphql: bool,
+    /// The logins found for emails keyed by lowercase email, as the search
+    /// API has a low rate limit and the same owners appear in many repos
+    email_logins: Mutex<HashMap<String, Option<String>>>,
 }
 
 impl Client {
-    pub fn new(github_api_token: Option<String>) -> Self {
-        Self { github_api_token }
+    pub fn new(options: GitHubOptions) -> eyre::Result<Self> {
+        let GitHubOptions {
+            github_api_token,
+            github_api_url,
+            github_raw_url,
+            cache_dir,
+            no_cache,
+            github_graphql,
+            github_app_id,
+            github_app_key_path,
+            installation_id,
+        } = options;
+        let auth = match (
+            github_api_token,
+            github_app_id,
+            github_app_key_path,
+            installation_id,
+        ) {
+            (None, None, None, None) => auth::Auth::Anonymous,
+            (Some(token), None, None, None) => auth::Auth::Token(token),
+            (None, Some(app_id), Some(key_path), Some(installation_id)) => {
+                auth::Auth::App(auth::App::new(app_id, installation_id, &key_path)?)
+            }
+            (Some(_), _, _, _) => {
+                return Err(eyre!(
+                    "An API token cannot be used at the same time as GitHub App authentication"
+                ))
+            }
+            _ => {
+                return Err(eyre!(
+                    "GitHub App authentication requires an app id, a private key path and an installation id"
+                ))
+            }
+        };
+        // The cache is only used when asked for, as a shared default
+        // directory could be written to by unrelated runs
+        let cache = match (no_cache, cache_dir) {
+            (false, Some(directory)) => Some(cache::Cache::new(directory)),
+            (true, _) | (false, None) => None,
+        };
+        Ok(Self {
+            auth,
+            api_url: github_api_url.trim_end_matches('/').to_string(),
+            raw_url: github_raw_url.trim_end_matches('/').to_string(),
+            http: reqwest::Client::new(),
+            cache,
+            graphql: github_graphql,
+            email_logins: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Whether organisation data should be fetched in bulk with the GraphQL
+    /// API rather than with a request per repository.
+    pub fn uses_graphql(&self) -> bool {
+        self.graphql
+    }
+
+    /// Whether requests are authenticated, which some APIs require.
+    pub fn is_authenticated(&self) -> bool {
+        !matches!(self.auth, auth::Auth::Anonymous)
     }
 
-    // https://docs.github.com/en/free-pro-team@latest/rest/reference/orgs#members
+    /// The members of an organisation who have made their membership public.
+    // https://docs.github.com/en/rest/reference/orgs#list-public-organization-members
     pub async fn public_organisation_members(
         &self,
         organisation: &str,
     ) -> eyre::Result<HashSet<String>> {
+        let url = format!(
+            "{}/orgs/{}/public_members?per_page=100",
+            self.api_url, organisation
+        );
+        self.members(url)
+            .await
+            .wrap_err("Unable to get public members for organisation")
+    }
+
+    /// All members of an organisation, including those whose membership is
+    /// private. Private members are only included if the request is
+    /// authenticated as a member of the organisation, e.g. with a token with
+    /// the `read:org` scope.
+    // https://docs.github.com/en/rest/reference/orgs#list-organization-members
+    pub async fn organisation_members(&self, organisation: &str) -> eyre::Result<HashSet<String>> {
+        let url = format!(
+            "{}/orgs/{}/members?per_page=100",
+            self.api_url, organisation
+        );
+        self.members(url)
+            .await
+            .wrap_err("Unable to get members for organisation")
+    }
+
+    async fn members(&self, url: String) -> eyre::Result<HashSet<String>> {
         #[derive(Debug, serde::Deserialize)]
         pub struct Member {
             login: String,
+            id: u64,
+        }
+
+        let members: Vec<Member> = self.api_list(url).await?;
+
+        // Remember who the members are so that we can find them if they
+        // rename their account
+        if let Some(cache) = &self.cache {
+            let ids = members
+                .iter()
+                .map(|member| (member.login.to_lowercase(), member.id))
+                .collect();
+            if let Err(error) = cache.put_user_ids(ids).await {
+                eprintln!("Unable to cache user ids: {:?}", error);
+            }
+        }
+
+        Ok(members.into_iter().map(|member| member.login).collect())
+    }
+
+    /// What has become of the GitHub account with a login. Renamed accounts
+    /// can only be found if their id was recorded in the cache by an earlier
+    /// run, while they were still an organisation member. Without a recorded
+    /// id a missing login may have been renamed rather than deleted.
+    // https://docs.github.com/en/rest/reference/users#get-a-user
+    pub async fn account(&self, login: &str) -> eyre::Result<Account> {
+        #[derive(Debug, serde::Deserialize)]
+        struct User {
+            login: String,
+            id: u64,
+            #[serde(default)]
+            suspended_at: Option<String>,
+        }
+
+        let get_user = |url: String| async move {
+            let response = self
+                .get(&url, Api::Rest)
+                .await
+                .wrap_err(format!("Failed to get {}", url))?;
+            match response.status.as_u16() {
+                404 => Ok(None),
+                200 => serde_json::from_str::<User>(&response.body)
+                    .map(Some)
+                    .wrap_err("Unable to parse JSON response"),
+                status => Err(eyre!("HTTP status {} for url ({})", status, url)),
+            }
+        };
+
+        let known_id = match &self.cache {
+            Some(cache) => cache.user_ids().await.get(&login.to_lowercase()).copied(),
+            None => None,
+        };
+        let user = get_user(format!("{}/users/{}", self.api_url, login)).await?;
+        let known_id = match (user, known_id) {
+            // The login no longer belongs to the account we knew, which may
+            // have been renamed
+            (Some(user), Some(known_id)) if user.id != known_id => known_id,
+            (None, Some(known_id)) => known_id,
+            (Some(user), _) if user.suspended_at.is_some() => return Ok(Account::Suspended),
+            (Some(_), _) => return Ok(Account::Exists),
+            (None, None) => return Ok(Account::MissingOrRenamed),
+        };
+        match get_user(format!("{}/user/{}", self.api_url, known_id)).await? {
+            Some(user) => Ok(Account::Renamed(user.login)),
+            None => Ok(Account::Missing),
+        }
+    }
+
+    /// The members of a team, or `None` if there is no such team in the
+    /// organisation. Listing team members requires an authenticated request
+    /// from a member of the organisation.
+    // https://docs.github.com/en/free-pro-team@latest/rest/reference/teams#list-team-members
+    pub async fn team_members(
+        &self,
+        organisation: &str,
+        team: &str,
+    ) -> eyre::Result<Option<HashSet<String>>> {
+        #[derive(Debug, serde::Deserialize)]
+        pub struct Member {
+            login: String,
+        }
+
+        let url = format!(
+            "{}/orgs/{}/teams/{}/members?per_page=100",
+            self.api_url, organisation, team
+        );
+        let members = self.api_optional_list(url).await.wrap_err(format!(
+            "Unable to get members of team {}/{}",
+            organisation, team
+        ))?;
+        Ok(members.map(|members| {
+            members
+                .into_iter()
+                .map(|member: Member| member.login)
+                .collect()
+        }))
+    }
+
+    /// Find the login of the GitHub user with an email address, first by
+    /// searching users' public email addresses and then by searching commits
+    /// authored with the email address. Each email is only searched for once.
+    // https://docs.github.com/en/free-pro-team@latest/rest/reference/search#search-users
+    // https://docs.github.com/en/free-pro-team@latest/rest/reference/search#search-commits
+    pub async fn login_for_email(&self, email: &str) -> eyre::Result<Option<String>> {
+        let key = email.to_lowercase();
+        if let Some(login) = self.email_logins.lock().await.get(&key) {
+            return Ok(login.clone());
+        }
+        let login = self.search_login_for_email(email).await?;
+        let _ = self.email_logins.lock().await.insert(key, login.clone());
+        Ok(login)
+    }
+
+    async fn search_login_for_email(&self, email: &str) -> eyre::Result<Option<String>> {
+        #[derive(Debug, serde::Deserialize)]
+        pub struct SearchResults<Item> {
+            items: Vec<Item>,
+        }
+        #[derive(Debug, serde::Deserialize)]
+        pub struct User {
+            login: String,
+        }
+        #[derive(Debug, serde::Deserialize)]
+        pub struct Commit {
+            author: Option<User>,
+        }
+
+        let search = |kind: &str, query: String| {
+            reqwest::Url::parse_with_params(
+                &format!("{}/search/{}", self.api_url, kind),
+                &[("q", query), ("per_page", "1".to_string())],
+            )
+            .wrap_err("Unable to build search URL")
+        };
+
+        let url = search("users", format!("{} in:email", email))?;
+        let response = self.api_get_response(url.as_str()).await?;
+        let users: SearchResults<User> =
+            serde_json::from_str(&response.body).wrap_err("Unable to parse JSON response")?;
+        if let Some(user) = users.items.into_iter().next() {
+            return Ok(Some(user.login));
+        }
+
+        let url = search("commits", format!("author-email:{}", email))?;
+        let response = self.api_get_response(url.as_str()).await?;
+        let commits: SearchResults<Commit> =
+            serde_json::from_str(&response.body).wrap_err("Unable to parse JSON response")?;
+        Ok(commits
+            .items
+            .into_iter()
+            .find_map(|commit| commit.author)
+            .map(|user| user.login))
+    }
+
+    /// The permission a user has on a repo, one of `admin`, `write`, `read` or
+    /// `none`, or `None` if there is no such user. Requires an authenticated
+    /// request with push access to the repo.
+    // https://docs.github.com/en/rest/reference/repos#get-repository-permissions-for-a-user
+    pub async fn collaborator_permission(
+        &self,
+        org: &str,
+        repo: &str,
+        login: &str,
+    ) -> eyre::Result<Option<String>> {
+        #[derive(Debug, serde::Deserialize)]
+        struct Permission {
+            permission: String,
         }
 
         let url = format!(
-            "https://api.github.com/orgs/{}/members?per_page=100",
-            organisation
+            "{}/repos/{}/{}/collaborators/{}/permission",
+            self.api_url, org, repo, login
         );
+        let response = self
+            .get(&url, Api::Rest)
+            .await
+            .wrap_err(format!("Failed to get {}", url))?;
+        if response.status == 404 {
+            return Ok(None);
+        }
+        if !response.status.is_success() {
+            return Err(eyre!("HTTP status {} for url ({})", response.status, url));
+        }
+        let permission: Permission =
+            serde_json::from_str(&response.body).wrap_err("Unable to parse JSON response")?;
+        Ok(Some(permission.permission))
+    }
+
+    /// The teams with access to a repo keyed by their lowercase slug, along
+    /// with their permission: one of `pull`, `triage`, `push`, `maintain` or
+    /// `admin`.
+    // https://docs.github.com/en/rest/reference/repos#list-repository-teams
+    pub async fn repo_team_permissions(
+        &self,
+        org: &str,
+        repo: &str,
+    ) -> eyre::Result<HashMap<String, String>> {
+        #[derive(Debug, serde::Deserialize)]
+        struct Team {
+            slug: String,
+            permission: String,
+        }
+
+        let url = format!("{}/repos/{}/{}/teams?per_page=100", self.api_url, org, repo);
         Ok(self
             .api_list(url)
             .await
-            .wrap_err("Unable to get public members for organisation")?
+            .wrap_err("Unable to get repo teams")?
             .into_iter()
-            .map(|member: Member| member.login)
+            .map(|team: Team| (team.slug.to_lowercase(), team.permission))
             .collect())
     }
 
@@ -48,8 +359,8 @@ impl Client {
         organisation: &str,
     ) -> eyre::Result<HashMap<String, Repo>> {
         let url = format!(
-            "https://api.github.com/orgs/{}/repos?type=archived&per_page=100",
-            organisation
+            "{}/orgs/{}/repos?type=archived&per_page=100",
+            self.api_url, organisation
         );
         Ok(self
             .api_list(url)
@@ -60,6 +371,133 @@ impl Client {
             .collect())
     }
 
+    // https://docs.github.com/en/free-pro-team@latest/rest/reference/repos#get-a-repository
+    pub async fn repo(&self, organisation: &str, name: &str) -> eyre::Result<Repo> {
+        let url = format!("{}/repos/{}/{}", self.api_url, organisation, name);
+        let response = self
+            .api_get_response(&url)
+            .await
+            .wrap_err("Unable to get repo")?;
+        serde_json::from_str(&response.body).wrap_err("Unable to parse JSON response")
+    }
+
+    /// The paths of every file in a repo at the given branch. Trees that are
+    /// too large for GitHub to return in full are marked as truncated.
+    // https://docs.github.com/en/rest/reference/git#get-a-tree
+    pub async fn repo_tree(&self, org: &str, repo: &str, branch: &str) -> eyre::Result<RepoTree> {
+        #[derive(Debug, serde::Deserialize)]
+        struct Tree {
+            tree: Vec<Entry>,
+            truncated: bool,
+        }
+
+        #[derive(Debug, serde::Deserialize)]
+        struct Entry {
+            path: String,
+            #[serde(rename = "type")]
+            kind: String,
+        }
+
+        let url = format!(
+            "{}/repos/{}/{}/git/trees/{}?recursive=1",
+            self.api_url, org, repo, branch
+        );
+        let response = self
+            .api_get_response(&url)
+            .await
+            .wrap_err("Unable to get repo file tree")?;
+        let tree: Tree =
+            serde_json::from_str(&response.body).wrap_err("Unable to parse JSON response")?;
+        Ok(RepoTree {
+            paths: tree
+                .tree
+                .into_iter()
+                .filter(|entry| entry.kind == "blob")
+                .map(|entry| entry.path)
+                .collect(),
+            truncated: tree.truncated,
+        })
+    }
+
+    /// Fetch the metadata and CODEOWNERS file of every repository in the
+    /// organisation with a handful of paginated GraphQL queries.
+    // https://docs.github.com/en/graphql/reference/objects#repository
+    pub async fn organisation_repos_graphql(
+        &self,
+        organisation: &str,
+    ) -> eyre::Result<OrganisationRepos> {
+        let mut repos = OrganisationRepos::default();
+        let mut cursor = None;
+        loop {
+            let variables = serde_json::json!({
+                "organisation": organisation,
+                "cursor": cursor,
+            });
+            let data: graphql::OrganisationReposData = self
+                .graphql(graphql::ORGANISATION_REPOS_QUERY, variables)
+                .await
+                .wrap_err("Unable to get repos for organisation")?;
+            let repositories = data
+                .organization
+                .ok_or_else(|| eyre!("Organisation {} not found", organisation))?
+                .repositories;
+            repos.extend(repositories.nodes);
+            match repositories.page_info {
+                graphql::PageInfo {
+                    has_next_page: true,
+                    end_cursor: Some(end_cursor),
+                } => cursor = Some(end_cursor),
+                _ => return Ok(repos),
+            }
+        }
+    }
+
+    /// Perform a GraphQL query. The GraphQL API cannot be used anonymously so
+    /// this requires an API token or GitHub App.
+    async fn graphql<Data: DeserializeOwned>(
+        &self,
+        query: &str,
+        variables: serde_json::Value,
+    ) -> eyre::Result<Data> {
+        let token = self
+            .auth
+            .token(&self.http, &self.api_url)
+            .await?
+            .ok_or_else(|| eyre!("The GitHub GraphQL API requires authentication"))?;
+        let url = format!("{}/graphql", self.api_url);
+        let body = serde_json::json!({ "query": query, "variables": variables });
+
+        // Queries only read data so they are safe to retry
+        let response: graphql::Response<Data> = self
+            .send_with_retries(|| {
+                self.http
+                    .post(&url)
+                    .header("user-agent", "embark-oss")
+                    .header("authorization", format!("bearer {}", token))
+                    .json(&body)
+            })
+            .await
+            .wrap_err(format!("Failed to query {}", url))?
+            .error_for_status()?
+            .json()
+            .await
+            .wrap_err("Unable to parse GraphQL response")?;
+
+        if !response.errors.is_empty() {
+            return Err(eyre!(
+                "GraphQL query failed: {}",
+                response
+                    .errors
+                    .iter()
+                    .map(|error| &error.message)
+                    .join(", ")
+            ));
+        }
+        response
+            .data
+            .ok_or_else(|| eyre!("GraphQL response contained no data"))
+    }
+
     /// Perform a GET request to a paginated GitHub URL that returns a JSON array per
     /// page. All pages will be traversed and retuned as a single collection.
     async fn api_list<Json: DeserializeOwned>(&self, url: String) -> eyre::Result<Vec<Json>> {
@@ -67,33 +505,307 @@ impl Client {
         let mut next_url = Some(url);
         while let Some(url) = next_url {
             let response = self.api_get_response(&url).await?;
-            next_url = next_pagination_page(&response)?;
-            let items: Vec<Json> = response
-                .json()
-                .await
-                .wrap_err("Unable to parse JSON response")?;
+            next_url = response.next_pagination_page();
+            let items: Vec<Json> =
+                serde_json::from_str(&response.body).wrap_err("Unable to parse JSON response")?;
             collection.extend(items);
         }
 
         Ok(collection)
     }
 
-    async fn api_get_response(&self, url: &str) -> eyre::Result<reqwest::Response> {
-        let request = reqwest::Client::new()
-            .get(url)
-            .header("accept", "application/vnd.github.v3+json")
-            .header("user-agent", "embark-oss");
-        let request = match &self.github_api_token {
-            Some(token) => request.header("authorization", format!("token {}", token)),
-            _ => request,
-        };
-        let response = request
-            .send()
+    /// Like `api_list`, but returning `None` if the first page is not found.
+    async fn api_optional_list<Json: DeserializeOwned>(
+        &self,
+        url: String,
+    ) -> eyre::Result<Option<Vec<Json>>> {
+        let response = self
+            .get(&url, Api::Rest)
             .await
-            .wrap_err(format!("Failed to get {}", url))?
-            .error_for_status()?;
+            .wrap_err(format!("Failed to get {}", url))?;
+        if response.status == 404 {
+            return Ok(None);
+        }
+        if !response.status.is_success() {
+            return Err(eyre!("HTTP status {} for url ({})", response.status, url));
+        }
+        let mut collection: Vec<Json> =
+            serde_json::from_str(&response.body).wrap_err("Unable to parse JSON response")?;
+        if let Some(next_url) = response.next_pagination_page() {
+            collection.extend(self.api_list(next_url).await?);
+        }
+        Ok(Some(collection))
+    }
+
+    async fn api_get_response(&self, url: &str) -> eyre::Result<Fetched> {
+        let response = self
+            .get(url, Api::Rest)
+            .await
+            .wrap_err(format!("Failed to get {}", url))?;
+        if !response.status.is_success() {
+            return Err(eyre!("HTTP status {} for url ({})", response.status, url));
+        }
         Ok(response)
     }
+
+    fn request(&self, url: &str, api: Api, token: Option<&str>) -> reqwest::RequestBuilder {
+        match api {
+            Api::Raw => self.http.get(url),
+            Api::Rest => {
+                let request = self
+                    .http
+                    .get(url)
+                    .header("accept", "application/vnd.github.v3+json")
+                    .header("user-agent", "embark-oss");
+                match token {
+                    Some(token) => request.header("authorization", format!("token {}", token)),
+                    _ => request,
+                }
+            }
+        }
+    }
+
+    /// Send a GET request, making it conditional upon the cached response if
+    /// there is one. Successful responses are stored in the cache.
+    async fn get(&self, url: &str, api: Api) -> eyre::Result<Fetched> {
+        let token = match api {
+            Api::Rest => self.auth.token(&self.http, &self.api_url).await?,
+            Api::Raw => None,
+        };
+        let token = token.as_deref();
+        let cache = match &self.cache {
+            Some(cache) => cache,
+            None => {
+                let response = self
+                    .send_with_retries(|| self.request(url, api, token))
+                    .await?;
+                return Fetched::from_response(response).await;
+            }
+        };
+
+        // Authenticated requests may see more than anonymous ones, so they are
+        // cached separately
+        let key = match token {
+            Some(_) => format!("{} (authenticated)", url),
+            None => url.to_string(),
+        };
+        let cached = cache.get(&key).await.filter(cache::Entry::is_conditional);
+        let response = self
+            .send_with_retries(|| match &cached {
+                Some(entry) => conditional_request(self.request(url, api, token), entry),
+                None => self.request(url, api, token),
+            })
+            .await?;
+
+        if let (reqwest::StatusCode::NOT_MODIFIED, Some(entry)) = (response.status(), cached) {
+            return Ok(Fetched {
+                status: reqwest::StatusCode::OK,
+                link: entry.link,
+                body: entry.body,
+            });
+        }
+
+        let headers = response.headers();
+        let etag = header_string(headers, "etag");
+        let last_modified = header_string(headers, "last-modified");
+        let fetched = Fetched::from_response(response).await?;
+        if fetched.status.is_success() {
+            let entry = cache::Entry {
+                key,
+                etag,
+                last_modified,
+                link: fetched.link.clone(),
+                body: fetched.body.clone(),
+            };
+            if let Err(error) = cache.put(&entry).await {
+                eprintln!("Unable to cache response for {}: {:?}", url, error);
+            }
+        }
+        Ok(fetched)
+    }
+
+    /// Send a GET request, retrying if GitHub tells us we are rate limited or
+    /// if there is a transient error. Only use this for idempotent requests.
+    async fn send_with_retries(
+        &self,
+        request: impl Fn() -> reqwest::RequestBuilder,
+    ) -> eyre::Result<reqwest::Response> {
+        let mut attempt = 1;
+        loop {
+            let response = match request().send().await {
+                Ok(response) => response,
+                Err(error) if attempt < rate_limit::MAX_ATTEMPTS && is_transient(&error) => {
+                    tokio::time::sleep(rate_limit::backoff(attempt)).await;
+                    attempt += 1;
+                    continue;
+                }
+                Err(error) => return Err(error.into()),
+            };
+
+            let status = response.status();
+            match rate_limit::assess(status, response.headers(), attempt, SystemTime::now()) {
+                rate_limit::Outcome::Done => return Ok(response),
+                rate_limit::Outcome::RateLimited(error) => return Err(error.into()),
+                rate_limit::Outcome::Retry(wait) => {
+                    tokio::time::sleep(wait).await;
+                    attempt += 1;
+                }
+            }
+        }
+    }
+
+    pub async fn download_repo_file(
+        &self,
+        org: &str,
+        repo: &str,
+        branch: &str,
+        file: &str,
+    ) -> eyre::Result<String> {
+        let (_, response) = self.download_file(org, repo, branch, file).await?;
+        Ok(response.body)
+    }
+
+    /// Download a file from a repo, or `None` if there is no such file.
+    pub async fn download_optional_repo_file(
+        &self,
+        org: &str,
+        repo: &str,
+        branch: &str,
+        file: &str,
+    ) -> eyre::Result<Option<String>> {
+        let download = self.download_optional_file(org, repo, branch, file).await?;
+        Ok(download.map(|(_, response)| response.body))
+    }
+
+    /// Download every CODEOWNERS file in a repo, in the order GitHub looks
+    /// for them. GitHub only uses the first of these.
+    pub async fn download_codeowners_files(
+        &self,
+        org: &str,
+        repo: &str,
+        branch: &str,
+    ) -> eyre::Result<Vec<CodeOwnersFile>> {
+        let downloads = codeowners::LOCATIONS
+            .iter()
+            .map(|location| self.download_optional_file(org, repo, branch, location));
+        let mut files = Vec::new();
+        for (location, download) in codeowners::LOCATIONS
+            .iter()
+            .zip(futures::future::join_all(downloads).await)
+        {
+            if let Some((_, response)) = download? {
+                files.push(CodeOwnersFile::new(location, response.body));
+            }
+        }
+        Ok(files)
+    }
+
+    pub async fn download_repo_json_file<Json: DeserializeOwned>(
+        &self,
+        org: &str,
+        repo: &str,
+        branch: &str,
+        file: &str,
+    ) -> eyre::Result<Json> {
+        let (name, response) = self.download_file(org, repo, branch, file).await?;
+        serde_json::from_str(&response.body).wrap_err(eyre!("Failed to decode {}", name))
+    }
+
+    async fn download_file(
+        &self,
+        org: &str,
+        repo: &str,
+        branch: &str,
+        file: &str,
+    ) -> eyre::Result<(String, Fetched)> {
+        match self.download_optional_file(org, repo, branch, file).await? {
+            Some(download) => Ok(download),
+            None => Err(eyre!("Expected status code 200, got 404"))
+                .wrap_err("File not found in repo")
+                .wrap_err(format!("Unable to download {}/{}:{}", org, repo, file)),
+        }
+    }
+
+    /// Download a file from a repo, or `None` if there is no such file.
+    async fn download_optional_file(
+        &self,
+        org: &str,
+        repo: &str,
+        branch: &str,
+        file: &str,
+    ) -> eyre::Result<Option<(String, Fetched)>> {
+        let name = format!("{}/{}:{}", org, repo, file);
+        let url = format!("{}/{}/{}/{}/{}", self.raw_url, org, repo, branch, file);
+        let response = self
+            .get(&url, Api::Raw)
+            .await
+            .wrap_err(format!("Failed to download {}", name))?;
+
+        // Ensure the file was successfully downloaded
+        if response.status == 404 {
+            return Ok(None);
+        }
+        if response.status != 200 {
+            return Err(eyre!("Expected status code 200, got {}", response.status))
+                .wrap_err(format!("Unable to download {}", name))?;
+        }
+
+        Ok(Some((name, response)))
+    }
+}
+
+/// Which of GitHub's HTTP services a request is made to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Api {
+    /// The REST API, which requests are authenticated with if we have a token.
+    Rest,
+    /// Raw file content from repositories.
+    Raw,
+}
+
+/// A response whose body has been read, possibly served from the cache.
+#[derive(Debug)]
+struct Fetched {
+    status: reqwest::StatusCode,
+    link: Option<String>,
+    body: String,
+}
+
+impl Fetched {
+    async fn from_response(response: reqwest::Response) -> eyre::Result<Self> {
+        let status = response.status();
+        let link = header_string(response.headers(), "link");
+        let body = response
+            .text()
+            .await
+            .wrap_err("Unable to read response body")?;
+        Ok(Self { status, link, body })
+    }
+
+    fn next_pagination_page(&self) -> Option<String> {
+        self.link
+            .as_deref()?
+            .split(',')
+            .find_map(parse_next_link_url)
+    }
+}
+
+fn conditional_request(
+    request: reqwest::RequestBuilder,
+    entry: &cache::Entry,
+) -> reqwest::RequestBuilder {
+    let request = match &entry.etag {
+        Some(etag) => request.header("if-none-match", etag),
+        None => request,
+    };
+    match &entry.last_modified {
+        Some(last_modified) => request.header("if-modified-since", last_modified),
+        None => request,
+    }
+}
+
+fn header_string(headers: &reqwest::header::HeaderMap, name: &str) -> Option<String> {
+    headers.get(name)?.to_str().ok().map(String::from)
 }
 
 #[derive(Debug, PartialEq, Clone, serde::Deserialize)]
@@ -102,6 +814,9 @@ pub struct Repo {
     pub archived: bool,
     pub private: bool,
     pub fork: bool,
+    pub default_branch: String,
+    pub description: Option<String>,
+    pub language: Option<String>,
 }
 
 impl Repo {
@@ -113,75 +828,93 @@ impl Repo {
     }
 }
 
-pub async fn download_repo_file(
-    org: &str,
-    repo: &str,
-    branch: &str,
-    file: &str,
-) -> eyre::Result<String> {
-    let (name, response) = download_file(org, repo, branch, file).await?;
-    response
-        .text()
-        .await
-        .wrap_err(eyre!("Failed to decode {}", name))
+/// The organisation used for repos that are named without one.
+pub const DEFAULT_ORGANISATION: &str = "EmbarkStudios";
+
+/// A repository, named `org/repo` or just `repo` for one in the default
+/// organisation.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, serde::Deserialize)]
+#[serde(try_from = "String")]
+pub struct RepoId {
+    pub organisation: String,
+    pub name: String,
+}
+
+impl RepoId {
+    pub fn new(organisation: &str, name: &str) -> Self {
+        Self {
+            organisation: organisation.to_string(),
+            name: name.to_string(),
+        }
+    }
 }
 
-pub async fn download_repo_json_file<Json: DeserializeOwned>(
-    org: &str,
-    repo: &str,
-    branch: &str,
-    file: &str,
-) -> eyre::Result<Json> {
-    let (name, response) = download_file(org, repo, branch, file).await?;
-    response
-        .json()
-        .await
-        .wrap_err(eyre!("Failed to decode {}", name))
+impl std::str::FromStr for RepoId {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split('/').collect::<Vec<_>>().as_slice() {
+            [name] if !name.is_empty() => Ok(Self::new(DEFAULT_ORGANISATION, name)),
+            [organisation, name] if !organisation.is_empty() && !name.is_empty() => {
+                Ok(Self::new(organisation, name))
+            }
+            _ => Err(format!("`{}` is not of the form org/repo", s)),
+        }
+    }
 }
 
-pub async fn download_file(
-    org: &str,
-    repo: &str,
-    branch: &str,
-    file: &str,
-) -> eyre::Result<(String, reqwest::Response)> {
-    let path = format!("{}/{}/{}/{}", org, repo, branch, file);
-    let name = format!("{}/{}:{}", org, repo, file);
-    let url = format!("https://raw.githubusercontent.com/{}", path);
-    let response = reqwest::get(&url)
-        .await
-        .wrap_err(format!("Failed to download {}", name))?;
-
-    // Ensure the file was successfully downloaded
-    if response.status() == 404 {
-        return Err(eyre!("Expected status code 200, got 404"))
-            .wrap_err("File not found in repo")
-            .wrap_err(format!("Unable to download {}", name))?;
-    }
-    if response.status() != 200 {
-        return Err(eyre!("Expected status code 200, got {}", response.status()))
-            .wrap_err(format!("Unable to download {}", name))?;
-    }
-
-    Ok((name, response))
+impl std::convert::TryFrom<String> for RepoId {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
 }
 
-fn next_pagination_page(response: &reqwest::Response) -> eyre::Result<Option<String>> {
-    match response.headers().get("link") {
-        None => Ok(None),
-        Some(link) => Ok(next_pagination_page_from_link_header(link)
-            .wrap_err("Unable to find next pagination page url in link header")?),
+impl std::fmt::Display for RepoId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.organisation, self.name)
     }
 }
 
-fn next_pagination_page_from_link_header(
-    header: &reqwest::header::HeaderValue,
-) -> eyre::Result<Option<String>> {
-    Ok(header
-        .to_str()
-        .wrap_err("Header was not valid unicode")?
-        .split(',')
-        .find_map(parse_next_link_url))
+/// What has become of a GitHub account.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Account {
+    Exists,
+    Suspended,
+    /// The account now has a different login
+    Renamed(String),
+    /// The account has been deleted, or never existed
+    Missing,
+    /// No account has the login, and without its id it is not known whether
+    /// it was deleted or renamed
+    MissingOrRenamed,
+}
+
+/// The files in a repository.
+#[derive(Debug, PartialEq, Clone)]
+pub struct RepoTree {
+    pub paths: Vec<String>,
+    /// Whether GitHub left out some of the files because the repo is too
+    /// large.
+    pub truncated: bool,
+}
+
+/// Whether a repository permission, as returned by the collaborator or team
+/// APIs, allows pushing to the repository. GitHub ignores code owners without
+/// this.
+pub fn is_write_permission(permission: &str) -> bool {
+    matches!(permission, "admin" | "maintain" | "write" | "push")
+}
+
+/// Whether the branch has one of the names conventionally used for a default
+/// branch, otherwise it is worth mentioning when reporting on the repo.
+pub fn is_conventional_branch(branch: &str) -> bool {
+    matches!(branch, "main" | "master")
+}
+
+fn is_transient(error: &reqwest::Error) -> bool {
+    error.is_timeout() || error.is_connect()
 }
 
 fn parse_next_link_url(content: &str) -> Option<String> {
@@ -197,6 +930,25 @@ fn parse_next_link_url(content: &str) -> Option<String> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn repo_ids() {
+        assert_eq!(
+            "some-project".parse(),
+            Ok(RepoId::new("EmbarkStudios", "some-project"))
+        );
+        assert_eq!(
+            "OtherOrg/some-project".parse(),
+            Ok(RepoId::new("OtherOrg", "some-project"))
+        );
+        assert_eq!(
+            RepoId::new("OtherOrg", "some-project").to_string(),
+            "OtherOrg/some-project"
+        );
+        assert!("".parse::<RepoId>().is_err());
+        assert!("OtherOrg/".parse::<RepoId>().is_err());
+        assert!("a/b/c".parse::<RepoId>().is_err());
+    }
+
     #[test]
     fn test_parse_next_link_url() {
         assert_eq!(parse_next_link_url(""), None);
@@ -224,6 +976,9 @@ mod tests {
             archived: false,
             private: false,
             fork: false,
+            default_branch: "main".to_string(),
+            description: None,
+            language: None,
         }
     }
 
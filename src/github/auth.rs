@@ -0,0 +1,214 @@
+//! Authentication with the GitHub API, either with a personal access token or
+//! as a GitHub App installation.
+//!
+//! <https://docs.github.com/en/developers/apps/building-github-apps/authenticating-with-github-apps>
+
+use chrono::{DateTime, Duration, Utc};
+use eyre::{eyre, WrapErr};
+use std::path::Path;
+use tokio::sync::Mutex;
+
+/// Installation tokens are refreshed when they are this close to expiring so
+/// that a token never expires part way through a request.
+const REFRESH_MARGIN: Duration = Duration::minutes(5);
+
+/// GitHub rejects app JWTs that expire more than 10 minutes in the future.
+const JWT_LIFETIME: Duration = Duration::minutes(9);
+
+pub enum Auth {
+    Anonymous,
+    Token(String),
+    App(App),
+}
+
+impl Auth {
+    /// The token to authenticate requests with, if any. GitHub App
+    /// installation tokens are fetched or refreshed as required.
+    pub async fn token(
+        &self,
+        http: &reqwest::Client,
+        api_url: &str,
+    ) -> eyre::Result<Option<String>> {
+        match self {
+            Self::Anonymous => Ok(None),
+            Self::Token(token) => Ok(Some(token.clone())),
+            Self::App(app) => app.installation_token(http, api_url).await.map(Some),
+        }
+    }
+}
+
+/// A GitHub App installation that requests are made on behalf of.
+pub struct App {
+    app_id: u64,
+    installation_id: u64,
+    key: jsonwebtoken::EncodingKey,
+    token: Mutex<Option<InstallationToken>>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+struct InstallationToken {
+    token: String,
+    expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct Claims {
+    iat: i64,
+    exp: i64,
+    iss: String,
+}
+
+impl App {
+    pub fn new(app_id: u64, installation_id: u64, key_path: &Path) -> eyre::Result<Self> {
+        let pem = std::fs::read(key_path)
+            .wrap_err_with(|| format!("Unable to read {}", key_path.display()))?;
+        let key = jsonwebtoken::EncodingKey::from_rsa_pem(&pem)
+            .wrap_err("Unable to parse GitHub App private key")?;
+        Ok(Self {
+            app_id,
+            installation_id,
+            key,
+            token: Mutex::new(None),
+        })
+    }
+
+    async fn installation_token(
+        &self,
+        http: &reqwest::Client,
+        api_url: &str,
+    ) -> eyre::Result<String> {
+        // Holding the lock while refreshing ensures concurrent requests don't
+        // each fetch their own token
+        let mut token = self.token.lock().await;
+        match &*token {
+            Some(current) if current.expires_at - Utc::now() > REFRESH_MARGIN => {
+                Ok(current.token.clone())
+            }
+            _ => {
+                let fresh = self
+                    .fetch_installation_token(http, api_url)
+                    .await
+                    .wrap_err("Unable to authenticate as GitHub App installation")?;
+                let value = fresh.token.clone();
+                *token = Some(fresh);
+                Ok(value)
+            }
+        }
+    }
+
+    // https://docs.github.com/en/rest/reference/apps#create-an-installation-access-token-for-an-app
+    async fn fetch_installation_token(
+        &self,
+        http: &reqwest::Client,
+        api_url: &str,
+    ) -> eyre::Result<InstallationToken> {
+        let url = format!(
+            "{}/app/installations/{}/access_tokens",
+            api_url, self.installation_id
+        );
+        let response = http
+            .post(&url)
+            .header("accept", "application/vnd.github.v3+json")
+            .header("user-agent", "embark-oss")
+            .header("authorization", format!("Bearer {}", self.jwt()?))
+            .send()
+            .await
+            .wrap_err(format!("Failed to post {}", url))?;
+        if !response.status().is_success() {
+            return Err(eyre!("HTTP status {} for url ({})", response.status(), url));
+        }
+        response
+            .json()
+            .await
+            .wrap_err("Unable to parse installation token response")
+    }
+
+    /// A short lived JSON web token that identifies the app itself.
+    fn jwt(&self) -> eyre::Result<String> {
+        // Backdate the issue time to allow for clock drift
+        let now = Utc::now();
+        let claims = Claims {
+            iat: (now - Duration::seconds(60)).timestamp(),
+            exp: (now + JWT_LIFETIME).timestamp(),
+            iss: self.app_id.to_string(),
+        };
+        let header = jsonwebtoken::Header::new(jsonwebtoken::Algorithm::RS256);
+        jsonwebtoken::encode(&header, &claims, &self.key).wrap_err("Unable to sign GitHub App JWT")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+    fn make_app() -> App {
+        let key_path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("src/github/fixtures/github_app_test_key.pem");
+        App::new(1234, 42, &key_path).unwrap()
+    }
+
+    async fn mount_token_endpoint(server: &MockServer, expires_at: DateTime<Utc>, calls: u64) {
+        let body = serde_json::json!({ "token": "ghs_installation", "expires_at": expires_at });
+        Mock::given(matchers::method("POST"))
+            .and(matchers::path("/app/installations/42/access_tokens"))
+            .and(matchers::header_regex(
+                "authorization",
+                r"^Bearer [^.]+\.[^.]+\.[^.]+$",
+            ))
+            .respond_with(ResponseTemplate::new(201).set_body_json(body))
+            .expect(calls)
+            .mount(server)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn installation_token_is_reused_until_near_expiry() {
+        let server = MockServer::start().await;
+        mount_token_endpoint(&server, Utc::now() + Duration::hours(1), 1).await;
+
+        let auth = Auth::App(make_app());
+        let http = reqwest::Client::new();
+        for _ in 0..3 {
+            let token = auth.token(&http, &server.uri()).await.unwrap();
+            assert_eq!(token.as_deref(), Some("ghs_installation"));
+        }
+    }
+
+    #[tokio::test]
+    async fn installation_token_is_refreshed_before_expiry() {
+        let server = MockServer::start().await;
+        mount_token_endpoint(&server, Utc::now() + Duration::minutes(1), 2).await;
+
+        let auth = Auth::App(make_app());
+        let http = reqwest::Client::new();
+        for _ in 0..2 {
+            let token = auth.token(&http, &server.uri()).await.unwrap();
+            assert_eq!(token.as_deref(), Some("ghs_installation"));
+        }
+    }
+
+    #[tokio::test]
+    async fn rejected_app_is_an_error() {
+        let server = MockServer::start().await;
+        Mock::given(matchers::method("POST"))
+            .respond_with(ResponseTemplate::new(401))
+            .mount(&server)
+            .await;
+
+        let auth = Auth::App(make_app());
+        let error = auth
+            .token(&reqwest::Client::new(), &server.uri())
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Unable to authenticate as GitHub App installation"
+        );
+    }
+
+    #[test]
+    fn missing_private_key_is_an_error() {
+        assert!(App::new(1234, 42, Path::new("does/not/exist.pem")).is_err());
+    }
+}
@@ -0,0 +1,194 @@
+//! A persistent cache of GitHub responses.
+//!
+//! Responses are stored along with their `ETag` and `Last-Modified` headers so
+//! that later runs can make conditional requests. GitHub does not count `304
+//! Not Modified` responses against the rate limit, so this lets us run the
+//! tool frequently without using up our quota.
+
+use eyre::WrapErr;
+use sha2::{Digest, Sha256};
+use std::{collections::HashMap, path::PathBuf, sync::Arc};
+use tokio::sync::Mutex;
+
+/// The file that the ids of GitHub users are kept in.
+const USER_IDS_FILE: &str = "user-ids.json";
+
+#[derive(Debug, Clone)]
+pub struct Cache {
+    directory: PathBuf,
+    /// Held while the user ids are updated, as members of several
+    /// organisations are listed at once and each update rewrites the file.
+    user_ids_lock: Arc<Mutex<()>>,
+}
+
+/// A successful response stored in the cache.
+#[derive(Debug, PartialEq, Eq, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Entry {
+    /// The URL of the request, plus anything else that affects the response.
+    pub key: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    /// The pagination link header, if the response had one.
+    pub link: Option<String>,
+    pub body: String,
+}
+
+impl Entry {
+    /// Whether the entry can be used to make a conditional request.
+    pub fn is_conditional(&self) -> bool {
+        self.etag.is_some() || self.last_modified.is_some()
+    }
+}
+
+impl Cache {
+    pub fn new(directory: PathBuf) -> Self {
+        Self {
+            directory,
+            user_ids_lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Look up the entry for a key. Any problem reading the cache is treated
+    /// as a cache miss.
+    pub async fn get(&self, key: &str) -> Option<Entry> {
+        let bytes = tokio::fs::read(self.path(key)).await.ok()?;
+        let entry: Entry = serde_json::from_slice(&bytes).ok()?;
+        // Guard against the unlikely event of a hash collision
+        if entry.key == key {
+            Some(entry)
+        } else {
+            None
+        }
+    }
+
+    pub async fn put(&self, entry: &Entry) -> eyre::Result<()> {
+        let json = serde_json::to_vec(entry).wrap_err("Unable to serialise cache entry")?;
+        self.write(self.path(&entry.key), json).await
+    }
+
+    /// The ids of GitHub users seen in previous runs keyed by their lowercase
+    /// login. Logins can change but ids can't, so these are used to find
+    /// accounts that have been renamed.
+    pub async fn user_ids(&self) -> HashMap<String, u64> {
+        let bytes = match tokio::fs::read(self.directory.join(USER_IDS_FILE)).await {
+            Ok(bytes) => bytes,
+            Err(_) => return HashMap::new(),
+        };
+        serde_json::from_slice(&bytes).unwrap_or_default()
+    }
+
+    /// Record the ids of GitHub users, keeping those previously recorded.
+    pub async fn put_user_ids(&self, ids: HashMap<String, u64>) -> eyre::Result<()> {
+        let _guard = self.user_ids_lock.lock().await;
+        let mut known = self.user_ids().await;
+        known.extend(ids);
+        let json = serde_json::to_vec(&known).wrap_err("Unable to serialise user ids")?;
+        self.write(self.directory.join(USER_IDS_FILE), json).await
+    }
+
+    async fn write(&self, path: PathBuf, json: Vec<u8>) -> eyre::Result<()> {
+        tokio::fs::create_dir_all(&self.directory)
+            .await
+            .wrap_err_with(|| format!("Unable to create {}", self.directory.display()))?;
+
+        // Write to a temporary file first so a concurrent reader never sees a
+        // partially written entry. Each write has its own temporary file so
+        // that concurrent writers of the same entry don't clash.
+        let temporary = path.with_extension(format!("{:016x}.tmp", rand::random::<u64>()));
+        tokio::fs::write(&temporary, json)
+            .await
+            .wrap_err_with(|| format!("Unable to write {}", temporary.display()))?;
+        tokio::fs::rename(&temporary, &path)
+            .await
+            .wrap_err_with(|| format!("Unable to write {}", path.display()))
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        let hash = Sha256::digest(key.as_bytes());
+        self.directory.join(format!("{:x}.json", hash))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_entry(key: &str) -> Entry {
+        Entry {
+            key: key.to_string(),
+            etag: Some(r#""abc""#.to_string()),
+            last_modified: None,
+            link: None,
+            body: "body".to_string(),
+        }
+    }
+
+    /// A directory for a test's cache that no other test or test run uses.
+    fn unique_temp_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("{}-{:016x}", name, rand::random::<u64>()))
+    }
+
+    #[tokio::test]
+    async fn round_trip() {
+        let directory = unique_temp_dir("embark-oss-cache-round-trip-test");
+        let cache = Cache::new(directory.clone());
+        let url = "https://example.com/one";
+
+        assert_eq!(cache.get(url).await, None);
+        cache.put(&make_entry(url)).await.unwrap();
+        assert_eq!(cache.get(url).await, Some(make_entry(url)));
+        assert_eq!(cache.get("https://example.com/two").await, None);
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn user_ids_are_merged() {
+        let directory = unique_temp_dir("embark-oss-cache-user-ids-test");
+        let cache = Cache::new(directory.clone());
+        let ids = |pairs: &[(&str, u64)]| {
+            pairs
+                .iter()
+                .map(|(login, id)| (login.to_string(), *id))
+                .collect::<HashMap<_, _>>()
+        };
+
+        assert_eq!(cache.user_ids().await, HashMap::new());
+        cache
+            .put_user_ids(ids(&[("lpil", 1), ("arirawr", 2)]))
+            .await
+            .unwrap();
+        cache.put_user_ids(ids(&[("lpil", 3)])).await.unwrap();
+        assert_eq!(cache.user_ids().await, ids(&[("lpil", 3), ("arirawr", 2)]));
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn concurrent_user_id_updates_are_all_kept() {
+        let directory = unique_temp_dir("embark-oss-cache-concurrent-user-ids-test");
+        let cache = Cache::new(directory.clone());
+
+        let updates = (0..20).map(|id| {
+            let ids = std::iter::once((format!("user-{}", id), id)).collect();
+            cache.put_user_ids(ids)
+        });
+        let _ = futures::future::try_join_all(updates).await.unwrap();
+        assert_eq!(cache.user_ids().await.len(), 20);
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn paths_are_stable_per_url() {
+        let cache = Cache::new(PathBuf::from("cache"));
+        assert_eq!(
+            cache.path("https://example.com/one"),
+            cache.path("https://example.com/one")
+        );
+        assert_ne!(
+            cache.path("https://example.com/one"),
+            cache.path("https://example.com/two")
+        );
+    }
+}
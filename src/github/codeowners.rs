@@ -1,5 +1,62 @@
+mod lint;
+mod pattern;
+
 use eyre::{eyre, WrapErr};
-use std::collections::HashSet;
+use std::{collections::HashSet, fmt};
+
+pub use lint::{Diagnostic, Level};
+use pattern::Pattern;
+
+/// The paths GitHub looks for a CODEOWNERS file at, in the order it checks
+/// them. Only the first file found is used.
+///
+/// <https://docs.github.com/en/github/creating-cloning-and-archiving-repositories/about-code-owners#codeowners-file-location>
+pub const LOCATIONS: [&str; 3] = [".github/CODEOWNERS", "CODEOWNERS", "docs/CODEOWNERS"];
+
+/// A CODEOWNERS file found in a repository.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeOwnersFile {
+    /// The path of the file, one of `LOCATIONS`.
+    pub location: String,
+    pub text: String,
+}
+
+impl CodeOwnersFile {
+    pub fn new(location: &str, text: String) -> Self {
+        Self {
+            location: location.to_string(),
+            text,
+        }
+    }
+
+    /// Whether two files assign different owners. Files that can't be parsed
+    /// are compared by their text.
+    pub fn disagrees_with(&self, other: &Self) -> bool {
+        match (CodeOwners::new(&self.text), CodeOwners::new(&other.text)) {
+            (Ok(this), Ok(other)) => this.rules().ne(other.rules()),
+            _ => self.text.trim() != other.text.trim(),
+        }
+    }
+}
+
+/// Warnings for the CODEOWNERS files that GitHub ignores because a file with
+/// different rules is found first. The files must be in `LOCATIONS` order.
+pub fn ignored_codeowners_warnings(files: &[CodeOwnersFile]) -> Vec<String> {
+    let (used, ignored) = match files.split_first() {
+        Some(split) => split,
+        None => return Vec::new(),
+    };
+    ignored
+        .iter()
+        .filter(|file| file.disagrees_with(used))
+        .map(|file| {
+            format!(
+                "{} is ignored by GitHub and disagrees with {}, which takes precedence",
+                file.location, used.location
+            )
+        })
+        .collect()
+}
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct CodeOwners {
@@ -13,38 +70,120 @@ pub struct CodeOwners {
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct Assignment {
+    /// The 1-indexed line of the CODEOWNERS file the assignment is on.
+    line: usize,
     /// A git compatible glob that specifies which files this assignment applies to.
-    file_pattern: String,
+    file_pattern: Pattern,
     /// A collection of GitHub usernames or emails for the users that own this
-    /// code section.
-    owners: HashSet<String>,
+    /// code section. This may be empty, in which case the matching files have
+    /// no owner.
+    owners: HashSet<Owner>,
+}
+
+/// A user or team that owns a section of code.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, PartialOrd, Ord)]
+pub enum Owner {
+    /// A GitHub user, written as `@username`.
+    User(String),
+    /// A GitHub team, written as `@organisation/team-slug`.
+    Team { organisation: String, name: String },
+    /// A GitHub user identified by an email address associated with their
+    /// account, written as `user@example.com`.
+    Email(String),
+}
+
+impl fmt::Display for Owner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::User(name) => write!(f, "{}", name),
+            Self::Team { organisation, name } => write!(f, "{}/{}", organisation, name),
+            Self::Email(email) => write!(f, "{}", email),
+        }
+    }
 }
 
 impl Assignment {
-    pub fn from_line(line: &str) -> eyre::Result<Self> {
-        let mut iter = line.split_whitespace().map(String::from);
-        let file_pattern = iter
-            .next()
-            .ok_or_else(|| eyre!("No file pattern for code owners line"))?;
-        let owners = iter
+    pub fn from_line(number: usize, line: &str) -> eyre::Result<Self> {
+        let (file_pattern, owners) = split_pattern(line);
+        if file_pattern.is_empty() {
+            return Err(eyre!("No file pattern for code owners line"));
+        }
+        let owners = owners
+            .split_whitespace()
+            .map(String::from)
             .map(validate_name_format)
-            .collect::<eyre::Result<HashSet<String>>>()
+            .collect::<eyre::Result<HashSet<Owner>>>()
             .wrap_err_with(|| format!("Unable to parse code owners for {}", file_pattern))?;
-        if owners.is_empty() {
-            return Err(eyre!("File pattern `{}` has no owners", file_pattern));
-        }
         Ok(Self {
-            file_pattern,
+            line: number,
+            file_pattern: Pattern::new(file_pattern)?,
             owners,
         })
     }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn file_pattern(&self) -> &str {
+        self.file_pattern.as_str()
+    }
+
+    pub fn owners(&self) -> &HashSet<Owner> {
+        &self.owners
+    }
+
+    /// Whether the assignment's pattern matches a file path.
+    pub fn matches(&self, path: &str) -> bool {
+        self.file_pattern.matches(path)
+    }
 }
 
-fn validate_name_format(name: String) -> eyre::Result<String> {
-    if name.starts_with('@') {
-        Ok(name.trim_start_matches('@').to_string())
-    } else {
-        Err(eyre!("Code owner `{}` does not start with an @", name))
+/// Split a line into the file pattern and the rest of the line. Whitespace
+/// within the pattern can be escaped with a `\`.
+fn split_pattern(line: &str) -> (&str, &str) {
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            c if c.is_whitespace() => return (&line[..i], &line[i..]),
+            _ => (),
+        }
+    }
+    (line, "")
+}
+
+fn validate_name_format(name: String) -> eyre::Result<Owner> {
+    let handle = match name.strip_prefix('@') {
+        Some(handle) => handle,
+        None if is_email(&name) => return Ok(Owner::Email(name)),
+        None => {
+            return Err(eyre!(
+                "Code owner `{}` is not an @username, @org/team or email address",
+                name
+            ))
+        }
+    };
+    match handle.split_once('/') {
+        None => Ok(Owner::User(handle.to_string())),
+        Some((organisation, team)) if !organisation.is_empty() && !team.is_empty() => {
+            Ok(Owner::Team {
+                organisation: organisation.to_string(),
+                name: team.to_string(),
+            })
+        }
+        Some(_) => Err(eyre!(
+            "Code owner team `{}` is not of the form @org/team",
+            name
+        )),
+    }
+}
+
+fn is_email(name: &str) -> bool {
+    match name.split_once('@') {
+        Some((user, domain)) => !user.is_empty() && domain.contains('.') && !domain.contains('@'),
+        None => false,
     }
 }
 
@@ -52,20 +191,57 @@ impl CodeOwners {
     pub fn new(source: &str) -> eyre::Result<Self> {
         let assignments = source
             .lines()
-            .map(|line| line.trim())
-            .filter(|line| !line.starts_with('#'))
-            .filter(|line| !line.is_empty())
-            .map(Assignment::from_line)
+            .enumerate()
+            .map(|(index, line)| (index + 1, line.trim()))
+            .filter(|(_, line)| !line.starts_with('#'))
+            .filter(|(_, line)| !line.is_empty())
+            .map(|(number, line)| Assignment::from_line(number, line))
             .collect::<eyre::Result<_>>()?;
         Ok(Self { assignments })
     }
 
-    pub fn primary_maintainers(&self) -> Option<&HashSet<String>> {
+    /// Every problem found in a CODEOWNERS file, ordered by position. Unlike
+    /// `new` this does not stop at the first invalid line.
+    pub fn lint(source: &str) -> Vec<Diagnostic> {
+        lint::lint(source)
+    }
+
+    pub fn primary_maintainers(&self) -> Option<&HashSet<Owner>> {
         self.assignments
             .iter()
-            .find(|assignment| assignment.file_pattern == "*")
+            .find(|assignment| assignment.file_pattern.as_str() == "*")
             .map(|assignment| &assignment.owners)
     }
+
+    /// The assignment that determines the owners of a file, using the same
+    /// rules as GitHub. The last matching pattern in the file takes
+    /// precedence. `None` means no pattern matches the file, an assignment
+    /// without owners means the file has explicitly been left without one.
+    pub fn assignment_for(&self, path: &str) -> Option<&Assignment> {
+        self.assignments
+            .iter()
+            .rev()
+            .find(|assignment| assignment.file_pattern.matches(path))
+    }
+
+    /// The owners of a file, using the same rules as GitHub. See
+    /// `assignment_for` for what `None` and an empty set mean.
+    pub fn owners_of(&self, path: &str) -> Option<&HashSet<Owner>> {
+        self.assignment_for(path)
+            .map(|assignment| &assignment.owners)
+    }
+
+    /// The assignments in the order they appear in the file.
+    pub fn assignments(&self) -> &[Assignment] {
+        &self.assignments
+    }
+
+    /// The patterns and owners, ignoring where they are in the file.
+    fn rules(&self) -> impl Iterator<Item = (&Pattern, &HashSet<Owner>)> {
+        self.assignments
+            .iter()
+            .map(|assignment| (&assignment.file_pattern, &assignment.owners))
+    }
 }
 
 #[cfg(test)]
@@ -92,7 +268,8 @@ mod tests {
             CodeOwners::new("* @lpil").unwrap(),
             CodeOwners {
                 assignments: vec![Assignment {
-                    file_pattern: "*".to_string(),
+                    line: 1,
+                    file_pattern: Pattern::new("*").unwrap(),
                     owners: hashset(&["lpil"])
                 }]
             }
@@ -102,7 +279,8 @@ mod tests {
             CodeOwners::new("* @lpil @arirawr").unwrap(),
             CodeOwners {
                 assignments: vec![Assignment {
-                    file_pattern: "*".to_string(),
+                    line: 1,
+                    file_pattern: Pattern::new("*").unwrap(),
                     owners: hashset(&["lpil", "arirawr"])
                 }]
             }
@@ -126,19 +304,21 @@ right/ok @celialewis3     @soniasingla    \n"
             CodeOwners {
                 assignments: vec![
                     Assignment {
-                        file_pattern: "*".to_string(),
+                        line: 1,
+                        file_pattern: Pattern::new("*").unwrap(),
                         owners: ["lpil", "arirawr"]
                             .iter()
-                            .cloned()
-                            .map(String::from)
+                            .map(|name| Owner::User(name.to_string()))
                             .collect()
                     },
                     Assignment {
-                        file_pattern: "left".to_string(),
+                        line: 4,
+                        file_pattern: Pattern::new("left").unwrap(),
                         owners: hashset(&["XAMPPRocky"])
                     },
                     Assignment {
-                        file_pattern: "right/ok".to_string(),
+                        line: 6,
+                        file_pattern: Pattern::new("right/ok").unwrap(),
                         owners: hashset(&["soniasingla", "celialewis3"])
                     }
                 ]
@@ -146,7 +326,289 @@ right/ok @celialewis3     @soniasingla    \n"
         );
     }
 
-    fn hashset(members: &[&str]) -> HashSet<String> {
-        members.iter().cloned().map(String::from).collect()
+    #[test]
+    fn parsing_teams() {
+        assert_eq!(
+            CodeOwners::new("* @lpil @EmbarkStudios/rust-ecosystem-team").unwrap(),
+            CodeOwners {
+                assignments: vec![Assignment {
+                    line: 1,
+                    file_pattern: Pattern::new("*").unwrap(),
+                    owners: vec![
+                        Owner::User("lpil".to_string()),
+                        Owner::Team {
+                            organisation: "EmbarkStudios".to_string(),
+                            name: "rust-ecosystem-team".to_string(),
+                        },
+                    ]
+                    .into_iter()
+                    .collect()
+                }]
+            }
+        );
+
+        assert_eq!(
+            CodeOwners::new("* @EmbarkStudios/")
+                .unwrap_err()
+                .root_cause()
+                .to_string(),
+            "Code owner team `@EmbarkStudios/` is not of the form @org/team",
+        );
+    }
+
+    #[test]
+    fn parsing_emails() {
+        assert_eq!(
+            CodeOwners::new("* @lpil lpil@example.com").unwrap(),
+            CodeOwners {
+                assignments: vec![Assignment {
+                    line: 1,
+                    file_pattern: Pattern::new("*").unwrap(),
+                    owners: vec![
+                        Owner::User("lpil".to_string()),
+                        Owner::Email("lpil@example.com".to_string()),
+                    ]
+                    .into_iter()
+                    .collect()
+                }]
+            }
+        );
+
+        for invalid in &["example.com", "lpil@example", "lpil@@example.com"] {
+            assert_eq!(
+                CodeOwners::new(&format!("* {}", invalid))
+                    .unwrap_err()
+                    .root_cause()
+                    .to_string(),
+                format!(
+                    "Code owner `{}` is not an @username, @org/team or email address",
+                    invalid
+                ),
+            );
+        }
+    }
+
+    /// The example CODEOWNERS file from GitHub's documentation
+    /// <https://docs.github.com/en/github/creating-cloning-and-archiving-repositories/about-code-owners#example-of-a-codeowners-file>
+    const GITHUB_EXAMPLE: &str = r#"
+# This is a comment.
+# Each line is a file pattern followed by one or more owners.
+
+# These owners will be the default owners for everything in
+# the repo. Unless a later match takes precedence,
+# @global-owner1 and @global-owner2 will be requested for
+# review when someone opens a pull request.
+*       @global-owner1 @global-owner2
+
+# Order is important; the last matching pattern takes the most
+# precedence. When someone opens a pull request that only
+# modifies JS files, only @js-owner and not the global
+# owner(s) will be requested for a review.
+*.js    @js-owner
+
+# You can also use email addresses if you prefer. They'll be
+# used to look up users just like we do for commit author
+# emails.
+*.go docs@example.com
+
+# Teams can be specified as code owners as well. Teams should
+# be identified in the format @org/team-name. Teams must have
+# explicit write access to the repository. In this example,
+# the octocats team in the octo-org organization owns all .txt files.
+*.txt @octo-org/octocats
+
+# In this example, @doctocat owns any files in the build/logs
+# directory at the root of the repository and any of its
+# subdirectories.
+/build/logs/ @doctocat
+
+# The `docs/*` pattern will match files like
+# `docs/getting-started.md` but not further nested files like
+# `docs/build-app/troubleshooting.md`.
+docs/*  docs@example.com
+
+# In this example, @octocat owns any file in an apps directory
+# anywhere in your repository.
+apps/ @octocat
+
+# In this example, @doctocat owns any file in the `/docs`
+# directory in the root of your repository and any of its
+# subdirectories.
+/docs/ @doctocat
+
+# In this example, any change inside the `/scripts` directory
+# will require approval from @doctocat or @octocat.
+/scripts/ @doctocat @octocat
+
+# In this example, @octocat owns any file in a `/logs` directory such as
+# `/build/logs`, `/scripts/logs`, and `/deeply/nested/logs`. Any changes
+# in a `/logs` directory will require approval from @octocat.
+**/logs @octocat
+
+# In this example, @octocat owns any file in the `/apps`
+# directory in the root of your repository except for the `/apps/github`
+# subdirectory, as its owners are left empty.
+/apps/ @octocat
+/apps/github
+"#;
+
+    #[test]
+    fn ignored_files_that_disagree_are_warned_about() {
+        let file = |location, text: &str| CodeOwnersFile::new(location, text.to_string());
+        assert_eq!(ignored_codeowners_warnings(&[]), Vec::<String>::new());
+
+        // Comments and formatting don't change the rules
+        let files = [
+            file(".github/CODEOWNERS", "* @lpil\ndocs/ @arirawr\n"),
+            file("CODEOWNERS", "# Maintainers\n*   @lpil\n\ndocs/ @arirawr"),
+        ];
+        assert_eq!(ignored_codeowners_warnings(&files), Vec::<String>::new());
+
+        let files = [
+            file("CODEOWNERS", "* @lpil"),
+            file("docs/CODEOWNERS", "* @arirawr"),
+        ];
+        assert_eq!(
+            ignored_codeowners_warnings(&files),
+            vec![
+                "docs/CODEOWNERS is ignored by GitHub and disagrees with CODEOWNERS, \
+which takes precedence"
+            ]
+        );
+    }
+
+    #[test]
+    fn owners_of_github_example() {
+        let codeowners = CodeOwners::new(GITHUB_EXAMPLE).unwrap();
+        let cases: &[(&str, &[&str])] = &[
+            ("README.md", &["@global-owner1", "@global-owner2"]),
+            (
+                "src/deeply/nested/file.rs",
+                &["@global-owner1", "@global-owner2"],
+            ),
+            ("index.js", &["@js-owner"]),
+            ("src/lib/index.js", &["@js-owner"]),
+            ("main.go", &["docs@example.com"]),
+            ("notes.txt", &["@octo-org/octocats"]),
+            (
+                "build/output/today.log",
+                &["@global-owner1", "@global-owner2"],
+            ),
+            // `**/logs` comes later so takes precedence over `/build/logs/`
+            ("build/logs/today.log", &["@octocat"]),
+            ("build/logs/old/yesterday.log", &["@octocat"]),
+            ("docs/getting-started.md", &["@doctocat"]),
+            ("docs/build-app/troubleshooting.md", &["@doctocat"]),
+            ("src/apps/main.rs", &["@octocat"]),
+            ("apps/main.rs", &["@octocat"]),
+            ("scripts/deploy.sh", &["@doctocat", "@octocat"]),
+            ("build/logs", &["@octocat"]),
+            ("scripts/logs/output.txt", &["@octocat"]),
+            ("deeply/nested/logs/output", &["@octocat"]),
+            ("apps/github/main.rs", &[]),
+            ("apps/github", &[]),
+        ];
+        for (path, expected) in cases {
+            assert_eq!(
+                codeowners.owners_of(path),
+                Some(&owners(expected)),
+                "owners of {}",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn owners_of_nested_docs() {
+        // Without the later `/docs/` rule `docs/*` only matches direct children
+        let codeowners = CodeOwners::new("docs/* @docs-owner").unwrap();
+        assert_eq!(
+            codeowners.owners_of("docs/getting-started.md"),
+            Some(&hashset(&["docs-owner"]))
+        );
+        assert_eq!(
+            codeowners.owners_of("docs/build-app/troubleshooting.md"),
+            None
+        );
+        assert_eq!(codeowners.owners_of("src/docs/readme.md"), None);
+    }
+
+    #[test]
+    fn owners_of_anchoring() {
+        let codeowners = CodeOwners::new(
+            "
+/root-only.md @anchored
+anywhere.md @unanchored
+src/lib.rs @middle-slash
+",
+        )
+        .unwrap();
+        let owner = |path| codeowners.owners_of(path);
+        assert_eq!(owner("root-only.md"), Some(&hashset(&["anchored"])));
+        assert_eq!(owner("sub/root-only.md"), None);
+        assert_eq!(owner("anywhere.md"), Some(&hashset(&["unanchored"])));
+        assert_eq!(owner("a/b/anywhere.md"), Some(&hashset(&["unanchored"])));
+        assert_eq!(owner("src/lib.rs"), Some(&hashset(&["middle-slash"])));
+        assert_eq!(owner("crates/foo/src/lib.rs"), None);
+    }
+
+    #[test]
+    fn owners_of_wildcards() {
+        let codeowners = CodeOwners::new(
+            "
+crates/**/Cargo.toml @manifests
+assets/** @artists
+file?.txt @question
+",
+        )
+        .unwrap();
+        let owner = |path| codeowners.owners_of(path);
+        assert_eq!(owner("crates/Cargo.toml"), Some(&hashset(&["manifests"])));
+        assert_eq!(
+            owner("crates/foo/Cargo.toml"),
+            Some(&hashset(&["manifests"]))
+        );
+        assert_eq!(
+            owner("crates/foo/bar/Cargo.toml"),
+            Some(&hashset(&["manifests"]))
+        );
+        assert_eq!(owner("Cargo.toml"), None);
+        assert_eq!(owner("assets/a/b/c.png"), Some(&hashset(&["artists"])));
+        assert_eq!(owner("assets"), None);
+        assert_eq!(owner("file1.txt"), Some(&hashset(&["question"])));
+        assert_eq!(owner("file10.txt"), None);
+        assert_eq!(owner("file/.txt"), None);
+    }
+
+    #[test]
+    fn owners_of_escaped_characters() {
+        let codeowners = CodeOwners::new(
+            r"
+\#notes.md @hash
+with\ space.md @space
+literal\*.md @star
+",
+        )
+        .unwrap();
+        let owner = |path| codeowners.owners_of(path);
+        assert_eq!(owner("#notes.md"), Some(&hashset(&["hash"])));
+        assert_eq!(owner("with space.md"), Some(&hashset(&["space"])));
+        assert_eq!(owner("literal*.md"), Some(&hashset(&["star"])));
+        assert_eq!(owner("literally.md"), None);
+    }
+
+    /// Owners as they are written in a CODEOWNERS file.
+    fn owners(written: &[&str]) -> HashSet<Owner> {
+        written
+            .iter()
+            .map(|owner| validate_name_format(owner.to_string()).unwrap())
+            .collect()
+    }
+
+    fn hashset(members: &[&str]) -> HashSet<Owner> {
+        members
+            .iter()
+            .map(|name| Owner::User(name.to_string()))
+            .collect()
     }
 }
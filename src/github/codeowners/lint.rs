@@ -0,0 +1,254 @@
+//! Checks for CODEOWNERS files that report every problem found along with its
+//! position, rather than stopping at the first line that can't be parsed.
+
+use super::{pattern, split_pattern, validate_name_format, Owner, Pattern};
+use std::{collections::HashMap, fmt};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    /// GitHub will not use the file, or a rule will not work as intended.
+    Error,
+    /// The file works but contains something that is probably a mistake.
+    Warning,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub level: Level,
+    /// The 1-indexed line the problem was found on.
+    pub line: usize,
+    /// The 1-indexed column the problem was found at.
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let level = match self.level {
+            Level::Error => "error",
+            Level::Warning => "warning",
+        };
+        write!(
+            f,
+            "{}:{}: {}: {}",
+            self.line, self.column, level, self.message
+        )
+    }
+}
+
+/// A rule from the file along with its position.
+struct Rule<'a> {
+    line: usize,
+    column: usize,
+    source: &'a str,
+    pattern: Pattern,
+}
+
+pub fn lint(source: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut rules = Vec::new();
+
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let mut diagnostic = |level, offset: usize, message| {
+            diagnostics.push(Diagnostic {
+                level,
+                line: line_number,
+                column: line[..offset].chars().count() + 1,
+                message,
+            })
+        };
+
+        let start = line.len() - line.trim_start().len();
+        let content = line.trim();
+        if content.is_empty() || content.starts_with('#') {
+            continue;
+        }
+
+        let (file_pattern, rest) = split_pattern(content);
+        for (position, c) in pattern::unsupported_characters(file_pattern) {
+            let message = match c {
+                '!' => "Negated patterns are not supported by GitHub".to_string(),
+                c => format!(
+                    "Character ranges are not supported by GitHub, escape `{}` with `\\` to match it literally",
+                    c
+                ),
+            };
+            let offset = start
+                + file_pattern
+                    .chars()
+                    .take(position)
+                    .map(char::len_utf8)
+                    .sum::<usize>();
+            diagnostic(Level::Error, offset, message);
+        }
+
+        let mut seen_owners: Vec<Owner> = Vec::new();
+        for (offset, token) in words(rest) {
+            let offset = start + file_pattern.len() + offset;
+            if token.starts_with('#') {
+                diagnostic(
+                    Level::Error,
+                    offset,
+                    "Comments must be on their own line, GitHub treats this as an owner"
+                        .to_string(),
+                );
+                break;
+            }
+            match validate_name_format(token.to_string()) {
+                Ok(owner) if seen_owners.contains(&owner) => diagnostic(
+                    Level::Warning,
+                    offset,
+                    format!("Code owner `{}` is listed more than once", token),
+                ),
+                Ok(owner) => seen_owners.push(owner),
+                Err(error) => diagnostic(Level::Error, offset, error.to_string()),
+            }
+        }
+
+        if let Ok(pattern) = Pattern::new(file_pattern) {
+            rules.push(Rule {
+                line: line_number,
+                column: start + 1,
+                source: file_pattern,
+                pattern,
+            });
+        }
+    }
+
+    diagnostics.extend(overridden_rules(&rules));
+    diagnostics.sort_by_key(|diagnostic| (diagnostic.line, diagnostic.column, diagnostic.level));
+    diagnostics
+}
+
+/// Rules that never take effect because a later rule matches all the same
+/// files. The last matching rule takes precedence.
+fn overridden_rules(rules: &[Rule<'_>]) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut last_use: HashMap<&str, usize> = HashMap::new();
+    for (i, rule) in rules.iter().enumerate().rev() {
+        let message = if let Some(later) = last_use.get(rule.source).map(|&i| &rules[i]) {
+            format!(
+                "Pattern `{}` is used again on line {} so this rule never takes effect",
+                rule.source, later.line
+            )
+        } else if let Some(later) = rules[i + 1..]
+            .iter()
+            .find(|later| rule.pattern.is_shadowed_by(&later.pattern))
+        {
+            format!(
+                "Pattern `{}` is shadowed by `{}` on line {} so this rule never takes effect",
+                rule.source, later.source, later.line
+            )
+        } else {
+            let _ = last_use.insert(rule.source, i);
+            continue;
+        };
+        let _ = last_use.entry(rule.source).or_insert(i);
+        diagnostics.push(Diagnostic {
+            level: Level::Warning,
+            line: rule.line,
+            column: rule.column,
+            message,
+        });
+    }
+    diagnostics
+}
+
+/// The whitespace separated words in some text, along with their byte offset.
+fn words(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    text.split_whitespace()
+        .map(move |word| (word.as_ptr() as usize - text.as_ptr() as usize, word))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lint_strings(source: &str) -> Vec<String> {
+        lint(source).iter().map(Diagnostic::to_string).collect()
+    }
+
+    #[test]
+    fn valid_file() {
+        let source = "
+# Comment
+* @lpil @arirawr
+/docs/ docs@example.com
+/apps/github
+crates/ @EmbarkStudios/rust
+";
+        assert_eq!(lint_strings(source), Vec::<String>::new());
+    }
+
+    #[test]
+    fn every_problem_is_reported() {
+        let source = "* @lpil arirawr
+  docs/ @lpil # the docs
+!secret @lpil
+src/[ab].rs @lpil @lpil
+";
+        assert_eq!(
+            lint_strings(source),
+            vec![
+                "1:9: error: Code owner `arirawr` is not an @username, @org/team or email address",
+                "2:15: error: Comments must be on their own line, GitHub treats this as an owner",
+                "3:1: error: Negated patterns are not supported by GitHub",
+                "4:5: error: Character ranges are not supported by GitHub, escape `[` with `\\` to match it literally",
+                "4:8: error: Character ranges are not supported by GitHub, escape `]` with `\\` to match it literally",
+                "4:19: warning: Code owner `@lpil` is listed more than once",
+            ]
+        );
+    }
+
+    #[test]
+    fn escaped_characters_are_fine() {
+        assert_eq!(lint_strings(r"\!important.md @lpil"), Vec::<String>::new());
+        assert_eq!(lint_strings(r"file\[1\].md @lpil"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn duplicate_patterns() {
+        let source = "
+docs/ @lpil
+*.rs @arirawr
+docs/ @arirawr
+";
+        assert_eq!(
+            lint_strings(source),
+            vec!["2:1: warning: Pattern `docs/` is used again on line 4 so this rule never takes effect"]
+        );
+    }
+
+    #[test]
+    fn shadowed_rules() {
+        let source = "
+/docs/readme.md @lpil
+README.md @lpil
+/docs/ @arirawr
+*.rs @arirawr
+src/main.rs @arirawr
+*.md @XAMPPRocky
+";
+        assert_eq!(
+            lint_strings(source),
+            vec![
+                "2:1: warning: Pattern `/docs/readme.md` is shadowed by `/docs/` on line 4 so this rule never takes effect",
+                "3:1: warning: Pattern `README.md` is shadowed by `*.md` on line 7 so this rule never takes effect",
+            ]
+        );
+
+        let source = "
+docs/ @lpil
+*.rs @lpil
+* @arirawr
+";
+        assert_eq!(
+            lint_strings(source),
+            vec![
+                "2:1: warning: Pattern `docs/` is shadowed by `*` on line 4 so this rule never takes effect",
+                "3:1: warning: Pattern `*.rs` is shadowed by `*` on line 4 so this rule never takes effect",
+            ]
+        );
+    }
+}
@@ -0,0 +1,194 @@
+//! File patterns as used in CODEOWNERS files.
+//!
+//! These follow the same rules as `.gitignore` files with a few exceptions,
+//! as described in
+//! <https://docs.github.com/en/github/creating-cloning-and-archiving-repositories/about-code-owners#codeowners-syntax>
+//!
+//! - A pattern starting with `/`, or containing a `/` other than at the end,
+//!   is anchored to the root of the repository. Otherwise it matches at any
+//!   depth.
+//! - A pattern ending with `/` only matches directories, and so all the files
+//!   inside them.
+//! - `*` and `?` match within a single path segment, `**` matches any number
+//!   of segments.
+//! - A `\` escapes the following character.
+//! - Unlike gitignore, `docs/*` only matches files directly inside `docs`.
+//! - Negation with `!` and character ranges with `[ ]` are not supported by
+//!   GitHub so these characters match literally.
+
+use eyre::WrapErr;
+use regex::Regex;
+use std::fmt;
+
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    source: String,
+    regex: Regex,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token {
+    Literal(char),
+    Star,
+    Question,
+    Slash,
+}
+
+impl Pattern {
+    pub fn new(source: &str) -> eyre::Result<Self> {
+        let regex = Regex::new(&to_regex(&tokenize(source)))
+            .wrap_err_with(|| format!("Invalid file pattern `{}`", source))?;
+        Ok(Self {
+            source: source.to_string(),
+            regex,
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    /// Whether the pattern matches a file path, relative to the root of the
+    /// repository.
+    pub fn matches(&self, path: &str) -> bool {
+        self.regex.is_match(path.trim_start_matches('/'))
+    }
+
+    /// Whether every file matched by this pattern is also matched by a later
+    /// pattern, meaning this pattern never takes effect. This can only be
+    /// determined for patterns without wildcards, or when the later pattern
+    /// matches everything.
+    pub fn is_shadowed_by(&self, later: &Self) -> bool {
+        let everything = ["file", ".hidden", "dir/file", "a/b/c/file.ext"];
+        if everything.iter().all(|path| later.matches(path)) {
+            return true;
+        }
+
+        let tokens = tokenize(&self.source);
+        if tokens
+            .iter()
+            .any(|token| matches!(token, Token::Star | Token::Question))
+        {
+            return false;
+        }
+        let directory_only = tokens.last() == Some(&Token::Slash);
+        let path: String = tokens
+            .iter()
+            .map(|token| match token {
+                Token::Literal(c) => *c,
+                _ => '/',
+            })
+            .collect();
+        let anchored = path.starts_with('/') || path.trim_matches('/').contains('/');
+        let path = path.trim_matches('/');
+
+        let mut samples = vec![format!("{}/file", path)];
+        if !directory_only {
+            samples.push(path.to_string());
+        }
+        if !anchored {
+            let nested: Vec<_> = samples.iter().map(|s| format!("dir/{}", s)).collect();
+            samples.extend(nested);
+        }
+        samples
+            .iter()
+            .all(|path| self.matches(path) && later.matches(path))
+    }
+}
+
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+impl Eq for Pattern {}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+fn tokenize(source: &str) -> Vec<Token> {
+    let mut tokens = Vec::with_capacity(source.len());
+    let mut chars = source.chars();
+    while let Some(c) = chars.next() {
+        tokens.push(match c {
+            '\\' => Token::Literal(chars.next().unwrap_or('\\')),
+            '*' => Token::Star,
+            '?' => Token::Question,
+            '/' => Token::Slash,
+            c => Token::Literal(c),
+        });
+    }
+    tokens
+}
+
+/// The positions of any unescaped characters that have a special meaning in
+/// gitignore files but are not supported in CODEOWNERS files.
+pub fn unsupported_characters(source: &str) -> Vec<(usize, char)> {
+    let mut unsupported = Vec::new();
+    let mut escaped = false;
+    for (i, c) in source.chars().enumerate() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '!' if i == 0 => unsupported.push((i, c)),
+            '[' | ']' => unsupported.push((i, c)),
+            _ => (),
+        }
+    }
+    unsupported
+}
+
+fn to_regex(tokens: &[Token]) -> String {
+    let directory_only = tokens.last() == Some(&Token::Slash);
+    let leading_slash = tokens.first() == Some(&Token::Slash);
+    let tokens = match tokens {
+        [Token::Slash, rest @ ..] => rest,
+        tokens => tokens,
+    };
+    let tokens = match tokens {
+        [rest @ .., Token::Slash] => rest,
+        tokens => tokens,
+    };
+    let anchored = leading_slash || tokens.contains(&Token::Slash);
+
+    let segments: Vec<&[Token]> = tokens.split(|token| *token == Token::Slash).collect();
+    let last = segments.len() - 1;
+
+    let mut regex = String::from(if anchored { "^" } else { "^(?:.*/)?" });
+    for (i, segment) in segments.iter().enumerate() {
+        match *segment {
+            [Token::Star, Token::Star] if i == last => regex.push_str(".*"),
+            [Token::Star, Token::Star] => regex.push_str("(?:[^/]*/)*"),
+            segment => {
+                for token in segment {
+                    match token {
+                        Token::Star => regex.push_str("[^/]*"),
+                        Token::Question => regex.push_str("[^/]"),
+                        Token::Literal(c) => regex.push_str(&regex::escape(&c.to_string())),
+                        Token::Slash => unreachable!("segments do not contain slashes"),
+                    }
+                }
+                if i != last {
+                    regex.push('/');
+                }
+            }
+        }
+    }
+
+    match segments[last] {
+        // Everything inside has already been matched
+        [Token::Star, Token::Star] => (),
+        _ if directory_only => regex.push_str("/.*"),
+        // `dir/*` only matches the direct children of the directory
+        [Token::Star] if last > 0 => (),
+        // Otherwise the pattern matches a file, or a directory and everything
+        // inside it
+        _ => regex.push_str("(?:/.*)?"),
+    }
+    regex.push('$');
+    regex
+}
@@ -0,0 +1,223 @@
+//! Bulk fetching of organisation data using the GitHub GraphQL API.
+//!
+//! The REST API needs a request per repository to find each CODEOWNERS file,
+//! whereas GraphQL can return them along with the repository metadata 100
+//! repositories at a time.
+//!
+//! <https://docs.github.com/en/graphql>
+
+use super::{codeowners::LOCATIONS, CodeOwnersFile, Repo};
+use std::collections::HashMap;
+
+pub const ORGANISATION_REPOS_QUERY: &str = r#"
+query OrganisationRepos($organisation: String!, $cursor: String) {
+  organization(login: $organisation) {
+    repositories(first: 100, after: $cursor) {
+      pageInfo {
+        hasNextPage
+        endCursor
+      }
+      nodes {
+        name
+        isArchived
+        isPrivate
+        isFork
+        description
+        primaryLanguage {
+          name
+        }
+        defaultBranchRef {
+          name
+        }
+        codeowners: object(expression: "HEAD:.github/CODEOWNERS") {
+          ... on Blob {
+            text
+          }
+        }
+        rootCodeowners: object(expression: "HEAD:CODEOWNERS") {
+          ... on Blob {
+            text
+          }
+        }
+        docsCodeowners: object(expression: "HEAD:docs/CODEOWNERS") {
+          ... on Blob {
+            text
+          }
+        }
+      }
+    }
+  }
+}
+"#;
+
+/// Repository metadata and CODEOWNERS files for a whole organisation.
+#[derive(Debug, Default)]
+pub struct OrganisationRepos {
+    pub repos: HashMap<String, Repo>,
+    /// Each repo's CODEOWNERS files, in the order GitHub looks for them. This
+    /// is empty if the repo has no CODEOWNERS file.
+    pub codeowners: HashMap<String, Vec<CodeOwnersFile>>,
+}
+
+impl OrganisationRepos {
+    pub fn extend(&mut self, nodes: Vec<RepositoryNode>) {
+        for node in nodes {
+            let codeowners = LOCATIONS
+                .iter()
+                .zip(vec![
+                    node.codeowners,
+                    node.root_codeowners,
+                    node.docs_codeowners,
+                ])
+                .filter_map(|(location, blob)| {
+                    let text = blob.and_then(|blob| blob.text)?;
+                    Some(CodeOwnersFile::new(location, text))
+                })
+                .collect();
+            let _ = self.codeowners.insert(node.name.clone(), codeowners);
+            let repo = Repo {
+                name: node.name,
+                archived: node.is_archived,
+                private: node.is_private,
+                fork: node.is_fork,
+                // Empty repos have no default branch until the first commit
+                default_branch: node
+                    .default_branch_ref
+                    .map(|branch| branch.name)
+                    .unwrap_or_else(|| "main".to_string()),
+                description: node.description,
+                language: node.primary_language.map(|language| language.name),
+            };
+            let _ = self.repos.insert(repo.name.clone(), repo);
+        }
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct Response<Data> {
+    pub data: Option<Data>,
+    #[serde(default)]
+    pub errors: Vec<Error>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct Error {
+    pub message: String,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct OrganisationReposData {
+    pub organization: Option<Organisation>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct Organisation {
+    pub repositories: Connection<RepositoryNode>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct Connection<Node> {
+    #[serde(rename = "pageInfo")]
+    pub page_info: PageInfo,
+    pub nodes: Vec<Node>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PageInfo {
+    pub has_next_page: bool,
+    pub end_cursor: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RepositoryNode {
+    pub name: String,
+    pub is_archived: bool,
+    pub is_private: bool,
+    pub is_fork: bool,
+    pub description: Option<String>,
+    pub primary_language: Option<Named>,
+    pub default_branch_ref: Option<Named>,
+    pub codeowners: Option<Blob>,
+    #[serde(default)]
+    pub root_codeowners: Option<Blob>,
+    #[serde(default)]
+    pub docs_codeowners: Option<Blob>,
+}
+
+/// Any object of which we only want the name, such as a git ref or language.
+#[derive(Debug, serde::Deserialize)]
+pub struct Named {
+    pub name: String,
+}
+
+/// A git object. If the object at the path is not a blob (i.e. it is a
+/// directory) this will be empty.
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct Blob {
+    #[serde(default)]
+    pub text: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_recorded_response() {
+        let response: Response<OrganisationReposData> =
+            serde_json::from_str(include_str!("fixtures/graphql_repos_page_2.json")).unwrap();
+        assert!(response.errors.is_empty());
+        let repositories = response.data.unwrap().organization.unwrap().repositories;
+        assert!(!repositories.page_info.has_next_page);
+
+        let mut repos = OrganisationRepos::default();
+        repos.extend(repositories.nodes);
+        assert_eq!(
+            repos.repos.get("kajiya"),
+            Some(&Repo {
+                name: "kajiya".to_string(),
+                archived: false,
+                private: false,
+                fork: false,
+                default_branch: "main".to_string(),
+                description: Some(
+                    "Experimental real-time global illumination renderer".to_string()
+                ),
+                language: Some("Rust".to_string()),
+            })
+        );
+        assert_eq!(
+            repos.codeowners.get("kajiya"),
+            Some(&vec![
+                CodeOwnersFile::new(".github/CODEOWNERS", "* @h3r2tic\n".to_string()),
+                CodeOwnersFile::new("docs/CODEOWNERS", "* @h3r2tic\n".to_string()),
+            ])
+        );
+
+        // The CODEOWNERS path is a directory rather than a file, so only the
+        // file at the root is found
+        assert_eq!(
+            repos.codeowners.get("odd-one"),
+            Some(&vec![CodeOwnersFile::new(
+                "CODEOWNERS",
+                "* @lpil\n".to_string()
+            )])
+        );
+        assert_eq!(repos.repos["odd-one"].default_branch, "develop");
+        // There is no CODEOWNERS file
+        assert_eq!(repos.codeowners.get("old-thing"), Some(&vec![]));
+    }
+
+    #[test]
+    fn parse_recorded_error_response() {
+        let response: Response<OrganisationReposData> =
+            serde_json::from_str(include_str!("fixtures/graphql_error.json")).unwrap();
+        assert!(response.data.unwrap().organization.is_none());
+        assert_eq!(
+            response.errors[0].message,
+            "Could not resolve to an Organization with the login of 'NotAnOrg'."
+        );
+    }
+}
@@ -0,0 +1,216 @@
+//! Handling of GitHub rate limits and transient server errors.
+//!
+//! <https://docs.github.com/en/rest/overview/resources-in-the-rest-api#rate-limiting>
+
+use rand::Rng;
+use reqwest::{header::HeaderMap, StatusCode};
+use std::{
+    fmt,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// The maximum number of times a request is sent before giving up.
+pub const MAX_ATTEMPTS: u32 = 5;
+
+/// If GitHub asks us to wait longer than this for the rate limit to reset we
+/// give up rather than stalling the run.
+const MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(5 * 60);
+
+const BASE_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// The error returned when GitHub refused a request due to rate limiting and
+/// we were not able to wait for the limit to reset.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct RateLimited {
+    /// How long until GitHub said the rate limit resets, if known.
+    pub reset_in: Option<Duration>,
+}
+
+impl fmt::Display for RateLimited {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Rate limited by GitHub")?;
+        if let Some(reset_in) = self.reset_in {
+            write!(f, ", the limit resets in {} seconds", reset_in.as_secs())?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for RateLimited {}
+
+/// Whether the given error was caused by GitHub rate limiting.
+pub fn is_rate_limited(error: &eyre::Report) -> bool {
+    error.chain().any(|cause| cause.is::<RateLimited>())
+}
+
+/// What to do after receiving a response.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// The response is final and should be returned to the caller.
+    Done,
+    /// The request should be sent again after waiting.
+    Retry(Duration),
+    /// We have been rate limited and are not going to wait for the reset.
+    RateLimited(RateLimited),
+}
+
+/// Decide whether a request should be retried based upon the response status
+/// and GitHub's rate limit headers. `attempt` starts at 1.
+pub fn assess(status: StatusCode, headers: &HeaderMap, attempt: u32, now: SystemTime) -> Outcome {
+    let may_retry = attempt < MAX_ATTEMPTS;
+
+    if status == StatusCode::FORBIDDEN || status == StatusCode::TOO_MANY_REQUESTS {
+        let retry_after = header_u64(headers, "retry-after").map(Duration::from_secs);
+        let remaining = header_u64(headers, "x-ratelimit-remaining");
+        let reset_in = header_u64(headers, "x-ratelimit-reset").map(|reset| {
+            let reset = UNIX_EPOCH + Duration::from_secs(reset);
+            reset.duration_since(now).unwrap_or_default()
+        });
+
+        let wait = match (retry_after, remaining) {
+            // Secondary rate limits tell us exactly how long to wait
+            (Some(retry_after), _) => retry_after,
+            // Primary rate limit, wait until the reset time plus a little slack
+            (None, Some(0)) => reset_in.unwrap_or(MAX_BACKOFF) + Duration::from_secs(1),
+            (None, _) if status == StatusCode::TOO_MANY_REQUESTS => backoff(attempt),
+            // A plain 403 is a real permissions error and retrying won't help
+            (None, _) => return Outcome::Done,
+        };
+
+        if !may_retry || wait > MAX_RATE_LIMIT_WAIT {
+            return Outcome::RateLimited(RateLimited {
+                reset_in: reset_in.or(retry_after),
+            });
+        }
+        return Outcome::Retry(wait);
+    }
+
+    if status.is_server_error() && may_retry {
+        return Outcome::Retry(backoff(attempt));
+    }
+
+    Outcome::Done
+}
+
+/// Exponential backoff with jitter, so concurrent requests don't all retry at
+/// the same moment.
+pub fn backoff(attempt: u32) -> Duration {
+    let exponent = attempt.saturating_sub(1).min(16);
+    let ceiling = (BASE_BACKOFF * 2u32.pow(exponent)).min(MAX_BACKOFF);
+    let half = ceiling / 2;
+    let jitter = rand::thread_rng().gen_range(0..=half.as_millis() as u64);
+    half + Duration::from_millis(jitter)
+}
+
+fn header_u64(headers: &HeaderMap, name: &str) -> Option<u64> {
+    headers.get(name)?.to_str().ok()?.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            let _ = headers.insert(*name, HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    fn now() -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(1_000_000)
+    }
+
+    #[test]
+    fn successful_responses_are_done() {
+        let outcome = assess(StatusCode::OK, &HeaderMap::new(), 1, now());
+        assert_eq!(outcome, Outcome::Done);
+
+        let outcome = assess(StatusCode::NOT_FOUND, &HeaderMap::new(), 1, now());
+        assert_eq!(outcome, Outcome::Done);
+    }
+
+    #[test]
+    fn plain_forbidden_is_not_retried() {
+        let outcome = assess(StatusCode::FORBIDDEN, &HeaderMap::new(), 1, now());
+        assert_eq!(outcome, Outcome::Done);
+
+        // Quota remaining means the 403 is not due to the rate limit
+        let headers = headers(&[("x-ratelimit-remaining", "10")]);
+        let outcome = assess(StatusCode::FORBIDDEN, &headers, 1, now());
+        assert_eq!(outcome, Outcome::Done);
+    }
+
+    #[test]
+    fn retry_after_is_respected() {
+        let headers = headers(&[("retry-after", "30")]);
+        let outcome = assess(StatusCode::FORBIDDEN, &headers, 1, now());
+        assert_eq!(outcome, Outcome::Retry(Duration::from_secs(30)));
+
+        let outcome = assess(StatusCode::TOO_MANY_REQUESTS, &headers, 1, now());
+        assert_eq!(outcome, Outcome::Retry(Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn exhausted_quota_waits_for_reset() {
+        let headers = headers(&[
+            ("x-ratelimit-remaining", "0"),
+            ("x-ratelimit-reset", "1000060"),
+        ]);
+        let outcome = assess(StatusCode::FORBIDDEN, &headers, 1, now());
+        assert_eq!(outcome, Outcome::Retry(Duration::from_secs(61)));
+    }
+
+    #[test]
+    fn distant_reset_is_reported_as_rate_limited() {
+        let headers = headers(&[
+            ("x-ratelimit-remaining", "0"),
+            ("x-ratelimit-reset", "1003600"),
+        ]);
+        let outcome = assess(StatusCode::FORBIDDEN, &headers, 1, now());
+        assert_eq!(
+            outcome,
+            Outcome::RateLimited(RateLimited {
+                reset_in: Some(Duration::from_secs(3600))
+            })
+        );
+    }
+
+    #[test]
+    fn rate_limit_gives_up_after_max_attempts() {
+        let headers = headers(&[("retry-after", "1")]);
+        let outcome = assess(StatusCode::TOO_MANY_REQUESTS, &headers, MAX_ATTEMPTS, now());
+        assert_eq!(
+            outcome,
+            Outcome::RateLimited(RateLimited {
+                reset_in: Some(Duration::from_secs(1))
+            })
+        );
+    }
+
+    #[test]
+    fn server_errors_are_retried() {
+        let status = StatusCode::BAD_GATEWAY;
+        assert!(matches!(
+            assess(status, &HeaderMap::new(), 1, now()),
+            Outcome::Retry(_)
+        ));
+        assert_eq!(
+            assess(status, &HeaderMap::new(), MAX_ATTEMPTS, now()),
+            Outcome::Done
+        );
+    }
+
+    #[test]
+    fn backoff_grows_and_is_capped() {
+        for attempt in 1..20 {
+            let ceiling = (BASE_BACKOFF * 2u32.pow((attempt - 1).min(16))).min(MAX_BACKOFF);
+            let delay = backoff(attempt);
+            assert!(delay >= ceiling / 2);
+            assert!(delay <= ceiling);
+        }
+    }
+}
@@ -4,7 +4,7 @@
     clippy::doc_markdown,
     clippy::dbg_macro,
     clippy::todo,
-    clippy::empty_enum,
+    clippy::empty_enums,
     clippy::enum_glob_use,
     clippy::mem_forget,
     clippy::use_self,
@@ -13,9 +13,7 @@
     clippy::needless_borrow,
     clippy::match_wildcard_for_single_variants,
     clippy::if_let_mutex,
-    clippy::mismatched_target_os,
     clippy::await_holding_lock,
-    clippy::match_on_vec_items,
     clippy::imprecise_flops,
     clippy::suboptimal_flops,
     clippy::lossy_float_literal,
@@ -37,11 +35,13 @@
     nonstandard_style,
     unused_import_braces,
     unused_qualifications,
+    unexpected_cfgs,
     unused_results
 )]
 
 mod error;
 mod github;
+mod owners;
 mod policy;
 mod slack;
 mod validate;
@@ -52,11 +52,24 @@ use structopt::StructOpt;
 #[derive(StructOpt, Debug)]
 #[structopt(global_settings = &[AppSettings::ColoredHelp, AppSettings::VersionlessSubcommands])]
 enum Command {
-    #[structopt(about = "Validate all projects listed in Embark's Open Source website data.json")]
+    #[structopt(
+        about = "Validate all projects of the GitHub organisations listed in the policy file"
+    )]
     ValidateAll(ValidateAll),
 
-    #[structopt(about = "Validate one project from Embark's GitHub organisation")]
-    Validate { name: String },
+    #[structopt(about = "Validate one project from one of our GitHub organisations")]
+    Validate(Validate),
+
+    #[structopt(about = "Check a CODEOWNERS file for mistakes, reporting each with its position")]
+    LintCodeowners(LintCodeowners),
+
+    #[structopt(
+        about = "Report the files in a GitHub repository that lack an eligible code owner"
+    )]
+    CodeownersCoverage(CodeownersCoverage),
+
+    #[structopt(about = "List the checks made of each project")]
+    Checks,
 }
 
 #[derive(StructOpt, Debug)]
@@ -65,14 +78,173 @@ struct ValidateAll {
     #[structopt(long("slack-webhook-url"))]
     slack_webhook_url: Option<String>,
 
+    #[structopt(long("email-mapping"), parse(from_os_str))]
+    email_mapping: Option<std::path::PathBuf>,
+
+    /// The policy file listing exceptions to our guidelines
+    #[structopt(
+        long("policy"),
+        env("EMBARK_OSS_POLICY"),
+        default_value("policy.toml"),
+        parse(from_os_str)
+    )]
+    policy: std::path::PathBuf,
+
+    /// The least serious findings that fail validation and are sent to Slack
+    #[structopt(
+        long("fail-on"),
+        env("EMBARK_OSS_FAIL_ON"),
+        default_value("error"),
+        possible_values(&["error", "warning", "notice"])
+    )]
+    fail_on: validate::Severity,
+
+    #[structopt(flatten)]
+    github: GitHubOptions,
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(flatten)]
+struct Validate {
+    /// The repository, as org/repo, or just repo for one of Embark Studios'
+    repo: github::RepoId,
+
+    #[structopt(long("email-mapping"), parse(from_os_str))]
+    email_mapping: Option<std::path::PathBuf>,
+
+    /// The policy file listing exceptions to our guidelines
+    #[structopt(
+        long("policy"),
+        env("EMBARK_OSS_POLICY"),
+        default_value("policy.toml"),
+        parse(from_os_str)
+    )]
+    policy: std::path::PathBuf,
+
+    /// The least serious findings that fail validation and are sent to Slack
+    #[structopt(
+        long("fail-on"),
+        env("EMBARK_OSS_FAIL_ON"),
+        default_value("error"),
+        possible_values(&["error", "warning", "notice"])
+    )]
+    fail_on: validate::Severity,
+
+    #[structopt(flatten)]
+    github: GitHubOptions,
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(flatten)]
+struct LintCodeowners {
+    /// A repository whose CODEOWNERS file on the default branch is checked
+    #[structopt(required_unless("file"))]
+    repo: Option<github::RepoId>,
+
+    /// A local CODEOWNERS file to check instead of a repository's
+    #[structopt(long("file"), parse(from_os_str), conflicts_with("repo"))]
+    file: Option<std::path::PathBuf>,
+
+    #[structopt(flatten)]
+    github: GitHubOptions,
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(flatten)]
+struct CodeownersCoverage {
+    /// The repository, as org/repo, or just repo for one of Embark Studios'
+    repo: github::RepoId,
+
+    /// How to print the report
+    #[structopt(long("format"), default_value("table"), possible_values(&["table", "json"]))]
+    format: ReportFormat,
+
+    #[structopt(long("email-mapping"), parse(from_os_str))]
+    email_mapping: Option<std::path::PathBuf>,
+
+    /// The policy file listing exceptions to our guidelines
+    #[structopt(
+        long("policy"),
+        env("EMBARK_OSS_POLICY"),
+        default_value("policy.toml"),
+        parse(from_os_str)
+    )]
+    policy: std::path::PathBuf,
+
+    #[structopt(flatten)]
+    github: GitHubOptions,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ReportFormat {
+    Table,
+    Json,
+}
+
+impl std::str::FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "table" => Ok(Self::Table),
+            "json" => Ok(Self::Json),
+            _ => Err(format!("Unknown report format `{}`", s)),
+        }
+    }
+}
+
+#[derive(StructOpt, Debug, Clone)]
+struct GitHubOptions {
     #[structopt(long("github-api-token"))]
     github_api_token: Option<String>,
+
+    #[structopt(
+        long("github-api-url"),
+        env("EMBARK_OSS_GITHUB_API_URL"),
+        default_value("https://api.github.com")
+    )]
+    github_api_url: String,
+
+    #[structopt(
+        long("github-raw-url"),
+        env("EMBARK_OSS_GITHUB_RAW_URL"),
+        default_value("https://raw.githubusercontent.com")
+    )]
+    github_raw_url: String,
+
+    #[structopt(long("cache-dir"), env("EMBARK_OSS_CACHE_DIR"), parse(from_os_str))]
+    cache_dir: Option<std::path::PathBuf>,
+
+    #[structopt(long("no-cache"))]
+    no_cache: bool,
+
+    #[structopt(long("github-graphql"))]
+    github_graphql: bool,
+
+    #[structopt(long("github-app-id"), env("EMBARK_OSS_GITHUB_APP_ID"))]
+    github_app_id: Option<u64>,
+
+    #[structopt(
+        long("github-app-key-path"),
+        env("EMBARK_OSS_GITHUB_APP_KEY_PATH"),
+        parse(from_os_str)
+    )]
+    github_app_key_path: Option<std::path::PathBuf>,
+
+    #[structopt(long("installation-id"), env("EMBARK_OSS_GITHUB_INSTALLATION_ID"))]
+    installation_id: Option<u64>,
 }
 
 #[tokio::main]
 async fn main() -> eyre::Result<()> {
     match Command::from_args() {
         Command::ValidateAll(options) => validate::all(options).await,
-        Command::Validate { name } => validate::one(name).await,
+        Command::Validate(options) => validate::one(options).await,
+        Command::LintCodeowners(options) => owners::lint(options).await,
+        Command::CodeownersCoverage(options) => validate::codeowners_coverage(options).await,
+        Command::Checks => {
+            validate::list_checks();
+            Ok(())
+        }
     }
 }
@@ -0,0 +1,55 @@
+use crate::{github, LintCodeowners};
+use eyre::{eyre, WrapErr};
+
+/// Print every problem found in a CODEOWNERS file, either a local file or the
+/// one on the default branch of a repository.
+pub(crate) async fn lint(options: LintCodeowners) -> eyre::Result<()> {
+    let LintCodeowners { repo, file, github } = options;
+    let file = match (file, repo) {
+        (Some(path), _) => github::CodeOwnersFile {
+            text: std::fs::read_to_string(&path)
+                .wrap_err_with(|| format!("Unable to read {}", path.display()))?,
+            location: path.display().to_string(),
+        },
+        (None, Some(repo)) => {
+            let client = github::Client::new(github)?;
+            download_codeowners(&client, &repo).await?
+        }
+        (None, None) => return Err(eyre!("Either a repo or --file is required")),
+    };
+
+    let diagnostics = github::CodeOwners::lint(&file.text);
+    for diagnostic in &diagnostics {
+        println!("{}", diagnostic);
+    }
+    let errors = diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.level == github::Level::Error)
+        .count();
+    if errors > 0 {
+        return Err(eyre!("Found {} error(s) in {}", errors, file.location));
+    }
+    Ok(())
+}
+
+/// The CODEOWNERS file GitHub uses for a repo, which is the first one found on
+/// its default branch. Any others that disagree with it are warned about.
+async fn download_codeowners(
+    client: &github::Client,
+    repo: &github::RepoId,
+) -> eyre::Result<github::CodeOwnersFile> {
+    let branch = client
+        .repo(&repo.organisation, &repo.name)
+        .await?
+        .default_branch;
+    let files = client
+        .download_codeowners_files(&repo.organisation, &repo.name, &branch)
+        .await?;
+    for warning in github::ignored_codeowners_warnings(&files) {
+        eprintln!("warning: {}", warning);
+    }
+    files
+        .into_iter()
+        .next()
+        .ok_or_else(|| eyre!("No CODEOWNERS file found in {}", repo))
+}
@@ -1,22 +1,579 @@
-//! Policies for specific projects or users
-
-/// Generally we require that all maintainers of Embark Studios open source projects are
-/// part of the Embark org, but this list allows some explicit exceptions
-pub const ALLOWED_NON_EMBARK_MAINTAINERS: [&str; 3] = [
-    // Emil (https://github.com/emilk) worked at Embark and built 2 open source crates that he continues to co-maintain
-    // - https://github.com/EmbarkStudios/puffin
-    // - https://github.com/EmbarkStudios/poll-promise
-    "emilk",
-    // Keith (https://github.com/keith) is contributor-to and co-maintainer of the k8s-buildkite-plugin
-    // https://github.com/EmbarkStudios/k8s-buildkite-plugin
-    "keith",
-    // Tomasz (https://github.com/h3r2tic) worked at Embark and built kajiya that he contains to maintain as experimental codebase
-    "h3r2tic",
-];
-
-/// Some project might be public but not quite ready to be listed on the website
-pub const IGNORED_PROJECTS: [&str; 1] = [
-    // server-framework is still in development (and the name isn't final) so we don't it on the
-    // website yet.
-    "server-framework",
-];
+//! Policies for specific projects or users, loaded from a policy file so that
+//! exceptions can be changed without rebuilding the tool.
+//!
+//! ```toml
+//! version = 1
+//!
+//! # Checks that repos may not opt out of in their .github/embark-oss.toml
+//! required_checks = ["maintainers"]
+//!
+//! [[maintainers]]
+//! login = "someone"
+//! repos = ["some-project"]
+//! justification = "Built some-project at Embark and continues to maintain it"
+//! approved_by = "someone-else"
+//! expires = 2030-01-01
+//!
+//! [[ignored_projects]]
+//! project = "some-other-project"
+//! justification = "Not ready to be listed on the website yet"
+//! approved_by = "someone-else"
+//! ```
+
+use crate::github::{RepoId, DEFAULT_ORGANISATION};
+use chrono::{Duration, NaiveDate};
+use eyre::{eyre, WrapErr};
+use itertools::Itertools;
+use serde::{Deserialize, Deserializer};
+use std::{fmt, path::Path};
+
+/// The version of the policy file format this tool understands.
+const VERSION: u32 = 1;
+
+/// How many days before an exception expires we start warning about it.
+const EXPIRY_WARNING_DAYS: i64 = 30;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Policy {
+    version: u32,
+    /// The GitHub organisations whose projects are validated
+    #[serde(default = "default_organisations")]
+    organisations: Vec<Organisation>,
+    /// Generally we require that all maintainers of Embark Studios open
+    /// source projects are part of the Embark org, but this list allows some
+    /// explicit exceptions
+    #[serde(default)]
+    maintainers: Vec<MaintainerException>,
+    /// Some projects might be public but not quite ready to be listed on the
+    /// website
+    #[serde(default)]
+    ignored_projects: Vec<IgnoredProject>,
+    /// Checks that repos may not opt out of
+    #[serde(default)]
+    required_checks: Vec<CheckId>,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Self {
+            version: VERSION,
+            organisations: default_organisations(),
+            maintainers: Vec::new(),
+            ignored_projects: Vec::new(),
+            required_checks: Vec::new(),
+        }
+    }
+}
+
+/// A GitHub organisation whose projects are validated, along with where to
+/// find the lists its projects must be registered in.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Organisation {
+    pub name: String,
+    /// The opensource website data.json listing the organisation's projects.
+    /// Without one the website check is skipped.
+    pub website_data: Option<RepoFile>,
+    /// The README that the organisation's Rust projects must be listed in.
+    /// Without one the rust-ecosystem check is skipped.
+    pub rust_ecosystem_readme: Option<RepoFile>,
+    /// The repo that the organisation's projects are created from. Without
+    /// one the community-files check is skipped.
+    pub template: Option<Template>,
+}
+
+/// A template repo whose community health files, such as its code of
+/// conduct, projects must keep.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Template {
+    pub repo: RepoId,
+    #[serde(default = "default_branch")]
+    pub branch: String,
+    /// How much a project's copy of a file may differ from the template's,
+    /// as a fraction of their lines, before it is reported.
+    #[serde(default = "default_max_difference")]
+    pub max_difference: f64,
+}
+
+/// A file on a branch of a GitHub repository.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RepoFile {
+    pub repo: RepoId,
+    #[serde(default = "default_branch")]
+    pub branch: String,
+    pub path: String,
+}
+
+fn default_branch() -> String {
+    "main".to_string()
+}
+
+fn default_max_difference() -> f64 {
+    0.25
+}
+
+/// Embark Studios and its usual data sources, for policy files that don't
+/// list any organisations.
+fn default_organisations() -> Vec<Organisation> {
+    let file = |repo, path: &str| RepoFile {
+        repo: RepoId::new(DEFAULT_ORGANISATION, repo),
+        branch: default_branch(),
+        path: path.to_string(),
+    };
+    vec![Organisation {
+        name: DEFAULT_ORGANISATION.to_string(),
+        website_data: Some(file("opensource-website", "static/data/data.json")),
+        rust_ecosystem_readme: Some(file("rust-ecosystem", "README.md")),
+        template: Some(Template {
+            repo: RepoId::new(DEFAULT_ORGANISATION, "opensource-template"),
+            branch: default_branch(),
+            max_difference: default_max_difference(),
+        }),
+    }]
+}
+
+/// The names of the checks made of each project, used to refer to them in the
+/// policy file and repo configs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+#[serde(try_from = "String")]
+pub enum CheckId {
+    /// Projects must have a maintainer in their organisation
+    Maintainers,
+    /// Everyone named in CODEOWNERS must still be eligible to be a maintainer
+    StaleOwners,
+    /// Code owners must be able to write to the repo
+    WriteAccess,
+    /// Rust projects must be included in the rust-ecosystem README
+    RustEcosystem,
+    /// Projects must be included in the opensource website data.json
+    Website,
+    /// Projects must be dual licensed MIT OR Apache-2.0
+    License,
+    /// Projects must keep the community health files of their template
+    CommunityFiles,
+    /// Projects must resolve the placeholders and TODOs of their template
+    Placeholders,
+}
+
+impl CheckId {
+    pub const ALL: [Self; 8] = [
+        Self::Maintainers,
+        Self::StaleOwners,
+        Self::WriteAccess,
+        Self::RustEcosystem,
+        Self::Website,
+        Self::License,
+        Self::CommunityFiles,
+        Self::Placeholders,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Maintainers => "maintainers",
+            Self::StaleOwners => "stale-owners",
+            Self::WriteAccess => "write-access",
+            Self::RustEcosystem => "rust-ecosystem",
+            Self::Website => "website",
+            Self::License => "license",
+            Self::CommunityFiles => "community-files",
+            Self::Placeholders => "placeholders",
+        }
+    }
+}
+
+impl fmt::Display for CheckId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+// TOML table keys can only be deserialized from strings
+impl std::convert::TryFrom<String> for CheckId {
+    type Error = String;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|check| check.name() == name)
+            .ok_or_else(|| {
+                format!(
+                    "unknown check `{}`, expected one of: {}",
+                    name,
+                    Self::ALL.iter().join(", ")
+                )
+            })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MaintainerException {
+    login: String,
+    repos: Vec<RepoId>,
+    justification: String,
+    approved_by: String,
+    #[serde(default, deserialize_with = "deserialize_date")]
+    expires: Option<NaiveDate>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct IgnoredProject {
+    project: RepoId,
+    justification: String,
+    approved_by: String,
+    #[serde(default, deserialize_with = "deserialize_date")]
+    expires: Option<NaiveDate>,
+}
+
+/// The parts shared by every kind of exception, used to check and report on
+/// them.
+struct Exception<'a> {
+    description: String,
+    justification: &'a str,
+    approved_by: &'a str,
+    expires: Option<NaiveDate>,
+}
+
+impl Policy {
+    pub fn load(path: &Path) -> eyre::Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .wrap_err_with(|| format!("Unable to read policy file {}", path.display()))?;
+        Self::parse(&text)
+            .wrap_err_with(|| format!("Unable to parse policy file {}", path.display()))
+    }
+
+    pub fn parse(text: &str) -> eyre::Result<Self> {
+        let policy: Self = toml::from_str(text)?;
+        if policy.version != VERSION {
+            return Err(eyre!(
+                "Unsupported policy version {}, expected version {}",
+                policy.version,
+                VERSION
+            ));
+        }
+        if policy.organisations.is_empty() {
+            return Err(eyre!("No organisations are listed"));
+        }
+        if let Some(organisation) = policy.organisations.iter().find(|organisation| {
+            organisation
+                .template
+                .as_ref()
+                .is_some_and(|template| !(0.0..=1.0).contains(&template.max_difference))
+        }) {
+            return Err(eyre!(
+                "The template of the {} organisation has a max_difference outside 0 to 1",
+                organisation.name
+            ));
+        }
+        for exception in policy.exceptions() {
+            if exception.justification.trim().is_empty() {
+                return Err(eyre!("The {} has no justification", exception.description));
+            }
+            if exception.approved_by.trim().is_empty() {
+                return Err(eyre!(
+                    "The {} does not say who approved it",
+                    exception.description
+                ));
+            }
+        }
+        if let Some(exception) = policy.maintainers.iter().find(|e| e.repos.is_empty()) {
+            return Err(eyre!(
+                "The maintainer exception for {} does not apply to any repos",
+                exception.login
+            ));
+        }
+        Ok(policy)
+    }
+
+    pub fn organisations(&self) -> &[Organisation] {
+        &self.organisations
+    }
+
+    /// Whether a user who is not a member of the repo's organisation is
+    /// allowed to be a maintainer of it.
+    pub fn allows_maintainer(&self, login: &str, repo: &RepoId, today: NaiveDate) -> bool {
+        self.maintainer_repos(login, today)
+            .any(|approved| is_same_repo(approved, repo))
+    }
+
+    /// The repos a user who is not a member of their organisations is
+    /// allowed to maintain. Logins are case-insensitive, as on GitHub.
+    pub fn maintainer_repos<'a>(
+        &'a self,
+        login: &'a str,
+        today: NaiveDate,
+    ) -> impl Iterator<Item = &'a RepoId> + 'a {
+        self.maintainers
+            .iter()
+            .filter(move |exception| {
+                exception.login.eq_ignore_ascii_case(login) && is_active(exception.expires, today)
+            })
+            .flat_map(|exception| exception.repos.iter())
+    }
+
+    /// Whether repos may opt out of a check.
+    pub fn allows_skipping(&self, check: CheckId) -> bool {
+        !self.required_checks.contains(&check)
+    }
+
+    /// Whether a project is excluded from validation.
+    pub fn is_ignored(&self, project: &RepoId, today: NaiveDate) -> bool {
+        self.ignored_projects.iter().any(|exception| {
+            is_same_repo(&exception.project, project) && is_active(exception.expires, today)
+        })
+    }
+
+    /// Exceptions that expire soon and need to be renewed or removed.
+    pub fn expiring(&self, today: NaiveDate) -> Vec<String> {
+        self.exceptions()
+            .filter_map(|exception| {
+                let expires = exception.expires?;
+                let is_soon = expires - today <= Duration::days(EXPIRY_WARNING_DAYS);
+                (is_active(Some(expires), today) && is_soon).then(|| {
+                    format!(
+                        "The {} expires on {}, renew it or remove it",
+                        exception.description, expires
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// Exceptions that have expired and no longer apply.
+    pub fn expired(&self, today: NaiveDate) -> Vec<String> {
+        self.exceptions()
+            .filter_map(|exception| {
+                let expires = exception.expires?;
+                (!is_active(Some(expires), today)).then(|| {
+                    format!(
+                        "The {} expired on {}, renew it or remove it",
+                        exception.description, expires
+                    )
+                })
+            })
+            .collect()
+    }
+
+    fn exceptions(&self) -> impl Iterator<Item = Exception<'_>> {
+        let maintainers = self.maintainers.iter().map(|exception| Exception {
+            description: format!(
+                "maintainer exception for {} ({})",
+                exception.login,
+                exception.repos.iter().join(", ")
+            ),
+            justification: &exception.justification,
+            approved_by: &exception.approved_by,
+            expires: exception.expires,
+        });
+        let ignored_projects = self.ignored_projects.iter().map(|exception| Exception {
+            description: format!("ignored project exception for {}", exception.project),
+            justification: &exception.justification,
+            approved_by: &exception.approved_by,
+            expires: exception.expires,
+        });
+        maintainers.chain(ignored_projects)
+    }
+}
+
+/// GitHub organisation and repo names are case insensitive.
+fn is_same_repo(a: &RepoId, b: &RepoId) -> bool {
+    a.organisation.eq_ignore_ascii_case(&b.organisation) && a.name.eq_ignore_ascii_case(&b.name)
+}
+
+/// Exceptions apply up to but not including their expiry date.
+fn is_active(expires: Option<NaiveDate>, today: NaiveDate) -> bool {
+    match expires {
+        Some(expires) => today < expires,
+        None => true,
+    }
+}
+
+/// TOML has its own date type, so dates are converted via their string form.
+fn deserialize_date<'de, D>(deserializer: D) -> Result<Option<NaiveDate>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let date = toml::value::Datetime::deserialize(deserializer)?;
+    NaiveDate::parse_from_str(&date.to_string(), "%Y-%m-%d")
+        .map(Some)
+        .map_err(|_| serde::de::Error::custom(format!("expected a date, got {}", date)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(text: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap()
+    }
+
+    fn repo(name: &str) -> RepoId {
+        name.parse().unwrap()
+    }
+
+    const POLICY: &str = r#"
+version = 1
+
+[[maintainers]]
+login = "forever"
+repos = ["a", "Elsewhere/b"]
+justification = "Built a and b"
+approved_by = "lpil"
+
+[[maintainers]]
+login = "temporary"
+repos = ["c"]
+justification = "Helping out with c"
+approved_by = "lpil"
+expires = 2021-03-01
+
+[[ignored_projects]]
+project = "secret"
+justification = "Not ready yet"
+approved_by = "lpil"
+expires = 2021-01-01
+"#;
+
+    #[test]
+    fn policy_file_in_repo_is_valid() {
+        let _ = Policy::parse(include_str!("../policy.toml")).unwrap();
+    }
+
+    #[test]
+    fn exceptions_apply_until_they_expire() {
+        let policy = Policy::parse(POLICY).unwrap();
+        let today = date("2020-12-31");
+        assert!(policy.allows_maintainer("forever", &repo("a"), today));
+        assert!(policy.allows_maintainer("temporary", &repo("c"), today));
+        assert!(!policy.allows_maintainer("someone-else", &repo("a"), today));
+        assert!(policy.is_ignored(&repo("secret"), today));
+        assert!(!policy.is_ignored(&repo("forever"), today));
+
+        let today = date("2021-03-01");
+        assert!(policy.allows_maintainer("forever", &repo("a"), today));
+        assert!(!policy.allows_maintainer("temporary", &repo("c"), today));
+        assert!(!policy.is_ignored(&repo("secret"), today));
+    }
+
+    #[test]
+    fn required_checks_cannot_be_skipped() {
+        let policy = Policy::parse("version = 1\nrequired_checks = [\"maintainers\"]").unwrap();
+        assert!(!policy.allows_skipping(CheckId::Maintainers));
+        assert!(policy.allows_skipping(CheckId::Website));
+        assert!(Policy::default().allows_skipping(CheckId::Maintainers));
+        assert!(Policy::parse("version = 1\nrequired_checks = [\"unknown\"]").is_err());
+    }
+
+    #[test]
+    fn maintainer_exceptions_only_apply_to_their_repos() {
+        let policy = Policy::parse(POLICY).unwrap();
+        let today = date("2020-12-31");
+        assert!(policy.allows_maintainer("forever", &repo("Elsewhere/b"), today));
+        assert!(policy.allows_maintainer("forever", &repo("elsewhere/B"), today));
+        assert!(!policy.allows_maintainer("forever", &repo("b"), today));
+        assert!(!policy.allows_maintainer("forever", &repo("c"), today));
+        assert!(!policy.allows_maintainer("temporary", &repo("a"), today));
+        // GitHub logins are case-insensitive
+        assert!(policy.allows_maintainer("Forever", &repo("a"), today));
+        assert_eq!(policy.maintainer_repos("TEMPORARY", today).count(), 1);
+        assert_eq!(
+            policy
+                .maintainer_repos("forever", today)
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec!["EmbarkStudios/a", "Elsewhere/b"]
+        );
+        assert_eq!(
+            policy
+                .maintainer_repos("temporary", date("2021-03-01"))
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn expiry_is_reported() {
+        let policy = Policy::parse(POLICY).unwrap();
+        assert_eq!(policy.expiring(date("2020-11-01")), Vec::<String>::new());
+        assert_eq!(policy.expired(date("2020-11-01")), Vec::<String>::new());
+
+        assert_eq!(
+            policy.expiring(date("2020-12-15")),
+            vec!["The ignored project exception for EmbarkStudios/secret expires on 2021-01-01, renew it or remove it"]
+        );
+
+        let today = date("2021-02-01");
+        assert_eq!(
+            policy.expiring(today),
+            vec!["The maintainer exception for temporary (EmbarkStudios/c) expires on 2021-03-01, renew it or remove it"]
+        );
+        assert_eq!(
+            policy.expired(today),
+            vec!["The ignored project exception for EmbarkStudios/secret expired on 2021-01-01, renew it or remove it"]
+        );
+    }
+
+    #[test]
+    fn invalid_policies() {
+        let error = |text: &str| Policy::parse(text).unwrap_err().to_string();
+        assert_eq!(
+            error("version = 2"),
+            "Unsupported policy version 2, expected version 1"
+        );
+        assert_eq!(
+            error(
+                r#"
+version = 1
+[[maintainers]]
+login = "someone"
+repos = ["a"]
+justification = " "
+approved_by = "lpil"
+"#
+            ),
+            "The maintainer exception for someone (EmbarkStudios/a) has no justification"
+        );
+        assert_eq!(
+            error(
+                r#"
+version = 1
+[[ignored_projects]]
+project = "a"
+justification = "Not ready yet"
+approved_by = ""
+"#
+            ),
+            "The ignored project exception for EmbarkStudios/a does not say who approved it"
+        );
+        assert_eq!(
+            error(
+                r#"
+version = 1
+[[maintainers]]
+login = "someone"
+repos = []
+justification = "Helping out"
+approved_by = "lpil"
+"#
+            ),
+            "The maintainer exception for someone does not apply to any repos"
+        );
+        assert_eq!(
+            error(
+                r#"
+version = 1
+[[organisations]]
+name = "EmbarkStudios"
+template = { repo = "opensource-template", max_difference = 1.5 }
+"#
+            ),
+            "The template of the EmbarkStudios organisation has a max_difference outside 0 to 1"
+        );
+        assert!(Policy::parse("version = 1\nunknown = true").is_err());
+    }
+}
@@ -1,108 +1,259 @@
+mod checks;
 mod context;
+mod coverage;
+mod eligibility;
 mod project;
+mod repo_config;
+mod severity;
 
 #[cfg(test)]
 mod tests;
 
 use self::{context::*, project::Project};
-use crate::{policy::IGNORED_PROJECTS, slack, ValidateAll};
+pub(crate) use self::{coverage::codeowners_coverage, severity::Severity};
+use crate::{github, policy::Policy, slack, Validate, ValidateAll};
 use eyre::eyre;
 use itertools::Itertools;
 
-/// Validate all projects listed in the data.json of the Embark Open Source
-/// website.
+/// Validate all projects of every organisation in the policy file, both those
+/// listed on the organisation's website and its public repos.
 pub(crate) async fn all(options: ValidateAll) -> eyre::Result<()> {
     let ValidateAll {
         slack_webhook_url,
-        github_api_token,
+        email_mapping,
+        policy,
+        fail_on,
+        github,
     } = options;
+    let policy = Policy::load(&policy)?;
+    let client = github::Client::new(github)?;
 
     // Lookup required contextual information
-    let context = Context::get(github_api_token).await?;
+    let context = Context::get(&client, email_mapping.as_deref(), policy).await?;
+    let expired_exceptions = check_policy_expiry(&context);
+    print_unauthenticated_checks(&client);
 
     // Download list of maintained projects and then validate each one
     let futures = context
         .all_projects()
         .into_iter()
-        .filter(|project| !IGNORED_PROJECTS.contains(&project.as_str()))
+        .filter(|project| !context.policy.is_ignored(project, context.today))
         .map(Project::new)
-        .map(|project| project.validate(&context));
+        .map(|project| project.validate(&context, &client));
     let projects = futures::future::join_all(futures).await;
 
     // Print results
     projects.iter().for_each(print_status);
 
-    // Collected the projects with issues
+    // Projects we were rate limited on are not at fault, so they are kept
+    // separate from the projects with issues
+    let (rate_limited_projects, projects): (Vec<_>, Vec<_>) = projects
+        .into_iter()
+        .partition(|project| project.is_rate_limited());
+
+    // Collected the projects with issues serious enough to report
     let problem_projects: Vec<_> = projects
         .into_iter()
-        .filter(|project| project.has_errors())
+        .filter(|project| project.fails(fail_on))
         .collect();
 
-    // If there is no problem we are done and can return
-    if problem_projects.is_empty() {
-        return Ok(());
+    // Send a message to slack if a webhook URL has been given
+    let has_problems = !problem_projects.is_empty() || !expired_exceptions.is_empty();
+    if let (Some(url), true) = (&slack_webhook_url, has_problems) {
+        let blocks = slack_notification_blocks(&problem_projects, &expired_exceptions, fail_on);
+        slack::send_webhook(url, blocks).await?;
     }
 
-    // Send a message to slack if a webhook URL has been given
-    if let Some(url) = slack_webhook_url {
-        let blocks = slack_notification_blocks(problem_projects.as_slice());
-        slack::send_webhook(&url, blocks).await?;
+    if !rate_limited_projects.is_empty() {
+        return Err(eyre!(
+            "Rate limited by GitHub, unable to validate: {}",
+            rate_limited_projects
+                .iter()
+                .map(|project| &project.repo)
+                .join(", ")
+        ));
+    }
+
+    if !problem_projects.is_empty() {
+        return Err(eyre!("Not all projects conform to our guidelines"));
+    }
+
+    // Expired exceptions need renewing or removing even if no project is
+    // affected by them
+    if !expired_exceptions.is_empty() {
+        return Err(eyre!("Some policy exceptions have expired"));
     }
 
-    Err(eyre!("Not all projects conform to our guidelines"))
+    Ok(())
 }
 
-/// Validate a single project from the Embark Studios GitHub organisation.
-pub async fn one(project_name: String) -> eyre::Result<()> {
+/// Validate a single project from one of our GitHub organisations.
+pub async fn one(options: Validate) -> eyre::Result<()> {
+    let Validate {
+        repo,
+        email_mapping,
+        policy,
+        fail_on,
+        github,
+    } = options;
+    let policy = Policy::load(&policy)?;
+    let client = github::Client::new(github)?;
+
     // Lookup required contextual information
-    let context = Context::get(None).await?;
+    let context = Context::get(&client, email_mapping.as_deref(), policy).await?;
+    let expired_exceptions = check_policy_expiry(&context);
+    print_unauthenticated_checks(&client);
 
     // Validate project
-    let project = Project::new(project_name).validate(&context).await;
+    let _ = context.organisation(&repo.organisation)?;
+    let project = Project::new(repo).validate(&context, &client).await;
     print_status(&project);
-    if project.has_errors() {
+    if project.is_rate_limited() {
+        Err(eyre!(
+            "Rate limited by GitHub, unable to validate the project"
+        ))
+    } else if project.fails(fail_on) {
         Err(eyre!("The project does not conform to our guidelines"))
+    } else if !expired_exceptions.is_empty() {
+        Err(eyre!("Some policy exceptions have expired"))
     } else {
         Ok(())
     }
 }
 
+/// List every check made of each project, by the name used for it in the
+/// policy file and repo configs.
+pub(crate) fn list_checks() {
+    let width = checks::ALL
+        .iter()
+        .map(|check| check.id().to_string().len())
+        .max()
+        .unwrap_or(0);
+    for check in checks::ALL {
+        println!(
+            "{:width$}  {}",
+            check.id().to_string(),
+            check.description(),
+            width = width
+        );
+    }
+}
+
+/// Print the checks that are not run for any project as they require GitHub
+/// authentication that has not been given.
+fn print_unauthenticated_checks(client: &github::Client) {
+    if client.is_authenticated() {
+        return;
+    }
+    for check in checks::ALL.iter().filter(|check| {
+        check
+            .requires()
+            .contains(&checks::Requirement::Authentication)
+    }) {
+        println!(
+            "⚠️ The {} check is not run as it requires GitHub authentication",
+            check.id()
+        );
+    }
+}
+
+/// Print the policy exceptions that expire soon or have expired, returning
+/// the expired ones.
+fn check_policy_expiry(context: &Context) -> Vec<String> {
+    for warning in context.policy.expiring(context.today) {
+        println!("⚠️ {}", warning);
+    }
+    let expired = context.policy.expired(context.today);
+    for error in &expired {
+        println!("❌ {}", error);
+    }
+    expired
+}
+
 fn print_status(project: &Project) {
-    if let Some(errors) = project.errors_to_string(true) {
-        return print!("❌ {}\n{}\n", project.name, errors);
+    print_result(project);
+    for (severity, finding) in project.minor_findings() {
+        println!(
+            "    {} {}",
+            severity.icon(),
+            finding.replace('\n', "\n       ")
+        );
     }
+}
 
-    if let Ok(maintainers) = &project.maintainers {
-        return println!("✔️ {} ({})", project.name, maintainers.iter().join(", "));
+fn print_result(project: &Project) {
+    if let Some(errors) = project.errors_to_string(true) {
+        return print!("{} {}\n{}\n", Severity::Error.icon(), project.repo, errors);
     }
 
-    unreachable!();
+    let branch = match &project.default_branch {
+        Some(branch) if !github::is_conventional_branch(branch) => {
+            format!(" on branch `{}`", branch)
+        }
+        _ => String::new(),
+    };
+    println!(
+        "✔️ {} ({}){}",
+        project.repo,
+        project.maintainers.iter().join(", "),
+        branch
+    );
 }
 
-fn slack_notification_blocks(projects: &[Project]) -> Vec<slack::Block> {
+/// The Slack notification for the projects with findings at or above the
+/// threshold, only including those findings.
+fn slack_notification_blocks(
+    projects: &[Project],
+    expired_exceptions: &[String],
+    threshold: Severity,
+) -> Vec<slack::Block> {
     use slack::Block::{Divider, Text};
 
-    let head = "The following Embark open source projects have been found to \
+    let head = "The following open source projects have been found to \
 have maintainership issues.";
     let foot = "This message was generated by the \
 <https://github.com/EmbarkStudios/opensource/tree/main/tools/embark-oss|embark-oss tool> \
 on GitHub Actions.";
 
-    let mut blocks = Vec::with_capacity(projects.len() + 4);
+    let mut blocks = Vec::with_capacity(projects.len() + 5);
 
     blocks.push(Text(head.to_string()));
     blocks.push(Divider);
-    blocks.extend(projects.iter().flat_map(slack_project_block));
+    blocks.extend(
+        projects
+            .iter()
+            .flat_map(|project| slack_project_block(project, threshold)),
+    );
+    if !expired_exceptions.is_empty() {
+        let text = format!(
+            "{} *Expired policy exceptions*\n```{}```",
+            Severity::Error.slack_emoji(),
+            expired_exceptions.join("\n")
+        );
+        blocks.push(Text(text));
+    }
     blocks.push(Divider);
     blocks.push(Text(foot.to_string()));
     blocks
 }
 
-fn slack_project_block(project: &Project) -> Option<slack::Block> {
-    let text = format!(
-        ":red_circle: *<https://github.com/EmbarkStudios/{name}|{name}>*\n```{error}```",
-        name = &project.name,
-        error = project.errors_to_string(false)?,
+/// A block for a project naming it with an emoji for its most serious
+/// finding, followed by its errors as code and then its other findings each
+/// marked with their own emoji.
+fn slack_project_block(project: &Project, threshold: Severity) -> Option<slack::Block> {
+    let severity = project.worst_severity().filter(|s| *s >= threshold)?;
+    let mut text = format!(
+        "{emoji} *<https://github.com/{repo}|{repo}>*",
+        emoji = severity.slack_emoji(),
+        repo = &project.repo,
     );
+    if let Some(errors) = project.errors_to_string(false) {
+        text.push_str(&format!("\n```{}```", errors));
+    }
+    for (severity, finding) in project.minor_findings() {
+        if severity >= threshold {
+            text.push_str(&format!("\n{} {}", severity.slack_emoji(), finding));
+        }
+    }
     Some(slack::Block::Text(text))
 }
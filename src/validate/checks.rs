@@ -0,0 +1,191 @@
+//! The checks made of each project. Each check is a [`Check`] in the
+//! [`ALL`] registry, which `Project::validate` runs in order.
+
+pub mod community_files;
+pub mod license;
+pub mod maintainers;
+pub mod placeholders;
+pub mod rust_ecosystem;
+pub mod stale_owners;
+pub mod website;
+pub mod write_access;
+
+use super::{
+    context::Context,
+    eligibility::{owners_eligibility, Eligibility},
+    repo_config::RepoConfig,
+    severity::Severity,
+};
+use crate::{
+    github::{self, Assignment, CodeOwnersFile, Owner},
+    policy::CheckId,
+};
+use futures::future::LocalBoxFuture;
+use itertools::Itertools;
+use std::collections::HashMap;
+use tokio::sync::OnceCell;
+
+/// Every check made of each project, in the order they are run and reported.
+pub const ALL: &[&dyn Check] = &[
+    &maintainers::Maintainers,
+    &stale_owners::StaleOwners,
+    &write_access::WriteAccess,
+    &website::Website,
+    &rust_ecosystem::RustEcosystem,
+    &license::License,
+    &community_files::CommunityFiles,
+    &placeholders::Placeholders,
+];
+
+/// A guideline that projects must follow.
+pub trait Check: Sync {
+    /// The name the check is referred to by in the policy file and repo
+    /// configs.
+    fn id(&self) -> CheckId;
+
+    /// What the check requires of a project.
+    fn description(&self) -> &'static str;
+
+    /// How serious it is for a project to fail the check.
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    /// What must be available for the check to run. If something is missing
+    /// the check is not run.
+    fn requires(&self) -> &'static [Requirement] {
+        &[]
+    }
+
+    /// Check the project, returning why it doesn't follow the guideline if
+    /// it does not.
+    fn run<'a>(&'a self, subject: &'a Subject<'a>) -> LocalBoxFuture<'a, eyre::Result<()>>;
+}
+
+/// Something a check needs that may not be available for every project.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Requirement {
+    /// The repo's default branch, on which its files are checked. Failing to
+    /// find it is reported by the project.
+    DefaultBranch,
+    /// The repo's CODEOWNERS files, and the default branch they were found
+    /// on. Failing to look them up is reported by the project.
+    Codeowners,
+    /// An authenticated GitHub client. Checks that are not run for lack of
+    /// one are reported once per run rather than for each project.
+    Authentication,
+}
+
+/// The project being checked, and everything known about it.
+pub struct Subject<'a> {
+    pub repo: &'a github::RepoId,
+    pub config: &'a RepoConfig,
+    pub context: &'a Context,
+    pub client: &'a github::Client,
+    pub(super) branch: Option<&'a str>,
+    pub(super) codeowners: Option<&'a [CodeOwnersFile]>,
+    pub(super) eligibility: OnceCell<HashMap<Owner, Eligibility>>,
+}
+
+impl<'a> Subject<'a> {
+    /// Whether the subject has what a check requires.
+    pub fn meets(&self, requirement: Requirement) -> bool {
+        match requirement {
+            Requirement::DefaultBranch => self.branch.is_some(),
+            Requirement::Codeowners => self.branch.is_some() && self.codeowners.is_some(),
+            Requirement::Authentication => self.client.is_authenticated(),
+        }
+    }
+
+    /// The repo's default branch, for checks that require
+    /// [`Requirement::DefaultBranch`] or [`Requirement::Codeowners`].
+    pub fn branch(&self) -> &'a str {
+        self.branch
+            .expect("checks using the default branch require it to have been found")
+    }
+
+    /// The repo's CODEOWNERS files in the order GitHub looks for them, for
+    /// checks that require [`Requirement::Codeowners`].
+    pub fn codeowners(&self) -> &'a [CodeOwnersFile] {
+        self.codeowners
+            .expect("checks using the CODEOWNERS files require them to have been found")
+    }
+
+    /// The eligibility of every owner in the CODEOWNERS file GitHub uses,
+    /// looked up once and shared by the checks that require
+    /// [`Requirement::Codeowners`].
+    pub async fn owners_eligibility(&self) -> eyre::Result<&HashMap<Owner, Eligibility>> {
+        self.eligibility
+            .get_or_try_init(|| {
+                used_owners_eligibility(self.context, self.client, self.repo, self.codeowners())
+            })
+            .await
+    }
+}
+
+/// The eligibility of every owner in the CODEOWNERS file GitHub uses, which
+/// is the first of `files`.
+pub async fn used_owners_eligibility(
+    context: &Context,
+    client: &github::Client,
+    repo: &github::RepoId,
+    files: &[CodeOwnersFile],
+) -> eyre::Result<HashMap<Owner, Eligibility>> {
+    let owners: Vec<_> = match parse_used_codeowners(files) {
+        Some((_, codeowners)) => codeowners
+            .assignments()
+            .iter()
+            .flat_map(|assignment| assignment.owners())
+            .cloned()
+            .collect(),
+        None => Vec::new(),
+    };
+    owners_eligibility(context, client, repo, &owners).await
+}
+
+/// The CODEOWNERS file that GitHub uses, and its parsed rules. A missing or
+/// invalid file is reported by the maintainers check so other checks of the
+/// file can skip it.
+pub fn parse_used_codeowners(
+    files: &[CodeOwnersFile],
+) -> Option<(&CodeOwnersFile, github::CodeOwners)> {
+    let file = files.first()?;
+    let codeowners = github::CodeOwners::new(&file.text).ok()?;
+    Some((file, codeowners))
+}
+
+/// A line for each owner with a problem, naming the rule they appear in.
+fn owner_problem_lines<'a>(
+    assignments: impl IntoIterator<Item = &'a Assignment>,
+    problems: &HashMap<Owner, String>,
+) -> Vec<String> {
+    assignments
+        .into_iter()
+        .flat_map(|assignment| {
+            assignment
+                .owners()
+                .iter()
+                .sorted()
+                .filter_map(move |owner| {
+                    Some(format!(
+                        "line {} `{}`: {} ({})",
+                        assignment.line(),
+                        assignment.file_pattern(),
+                        owner,
+                        problems.get(owner)?
+                    ))
+                })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_check_is_registered_once() {
+        let registered: Vec<_> = ALL.iter().map(|check| check.id()).sorted().collect();
+        assert_eq!(registered, CheckId::ALL.to_vec());
+    }
+}
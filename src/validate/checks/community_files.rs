@@ -0,0 +1,159 @@
+//! Projects must keep the community health files of the template repo they
+//! were created from, such as its code of conduct and issue templates.
+
+use super::{Check, Requirement, Subject};
+use crate::policy::CheckId;
+use eyre::{eyre, WrapErr};
+use futures::future::{FutureExt, LocalBoxFuture};
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::collections::HashMap;
+
+/// Projects' changelogs are their own, so they only need to exist.
+const CHANGELOG: &str = "CHANGELOG.md";
+
+#[derive(Debug)]
+pub struct CommunityFiles;
+
+impl Check for CommunityFiles {
+    fn id(&self) -> CheckId {
+        CheckId::CommunityFiles
+    }
+
+    fn description(&self) -> &'static str {
+        "Projects must keep the code of conduct, contributing guide, changelog and issue and \
+pull request templates of their template repo"
+    }
+
+    fn requires(&self) -> &'static [Requirement] {
+        &[Requirement::DefaultBranch]
+    }
+
+    fn run<'a>(&'a self, subject: &'a Subject<'a>) -> LocalBoxFuture<'a, eyre::Result<()>> {
+        async move {
+            let organisation = subject.context.organisation(&subject.repo.organisation)?;
+            let template = match &organisation.template {
+                Some(template) => template,
+                None => return Ok(()),
+            };
+            let mut problems = Vec::new();
+            for (path, template_text) in &template.files {
+                let text = subject
+                    .client
+                    .download_optional_repo_file(
+                        &subject.repo.organisation,
+                        &subject.repo.name,
+                        subject.branch(),
+                        path,
+                    )
+                    .await
+                    .wrap_err_with(|| format!("Unable to read {}", path))?;
+                let text = match text {
+                    Some(text) => text,
+                    None => {
+                        problems.push(format!("{} is missing", path));
+                        continue;
+                    }
+                };
+                if path == CHANGELOG {
+                    continue;
+                }
+                if let Some(problem) = covenant_version_problem(&text, template_text) {
+                    problems.push(format!("{} {}", path, problem));
+                    continue;
+                }
+                let difference = difference(&text, template_text);
+                if difference > template.max_difference {
+                    problems.push(format!(
+                        "{} differs from the template by {:.0}%, more than the {:.0}% allowed",
+                        path,
+                        difference * 100.0,
+                        template.max_difference * 100.0
+                    ));
+                }
+            }
+
+            if problems.is_empty() {
+                Ok(())
+            } else {
+                Err(eyre!(
+                    "Community health files missing or changed from {}:\n{}",
+                    template.repo,
+                    problems.join("\n")
+                ))
+            }
+        }
+        .boxed_local()
+    }
+}
+
+/// How much a file differs from the template's, as the fraction of their
+/// non-blank lines that are only in one of them.
+fn difference(text: &str, template: &str) -> f64 {
+    let lines = |text: &'_ str| {
+        let mut counts = HashMap::new();
+        for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            *counts.entry(line.to_string()).or_insert(0_usize) += 1;
+        }
+        counts
+    };
+    let (text, template) = (lines(text), lines(template));
+    let total = text.values().sum::<usize>() + template.values().sum::<usize>();
+    if total == 0 {
+        return 0.0;
+    }
+    let shared: usize = text
+        .iter()
+        .map(|(line, count)| *count.min(template.get(line).unwrap_or(&0)))
+        .sum();
+    (total - 2 * shared) as f64 / total as f64
+}
+
+/// Why a code of conduct is outdated, if it is adapted from a different
+/// version of the Contributor Covenant than the template's.
+fn covenant_version_problem(text: &str, template: &str) -> Option<String> {
+    lazy_static! {
+        static ref RE: Regex =
+            Regex::new(r"contributor-covenant\.org/version/(\d+)/(\d+)").unwrap();
+    }
+    let version = |text: &str| {
+        RE.captures(text)
+            .map(|captures| format!("{}.{}", &captures[1], &captures[2]))
+    };
+    match (version(text), version(template)) {
+        (Some(version), Some(expected)) if version != expected => Some(format!(
+            "uses version {} of the Contributor Covenant rather than {}",
+            version, expected
+        )),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn differences_are_a_fraction_of_lines() {
+        assert_eq!(difference("a\nb\n\nc", "  a\nb\nc\n"), 0.0);
+        assert_eq!(difference("a\nb", "a\nc"), 0.5);
+        assert_eq!(difference("a\na", "a"), 1.0 / 3.0);
+        assert_eq!(difference("", "a"), 1.0);
+        assert_eq!(difference("", ""), 0.0);
+    }
+
+    #[test]
+    fn covenant_versions() {
+        let code_of_conduct = include_str!("../../../CODE_OF_CONDUCT.md");
+        let newer = code_of_conduct.replace("version/1/4", "version/2/1");
+        assert_eq!(
+            covenant_version_problem(code_of_conduct, code_of_conduct),
+            None
+        );
+        assert_eq!(
+            covenant_version_problem(code_of_conduct, &newer),
+            Some("uses version 1.4 of the Contributor Covenant rather than 2.1".to_string())
+        );
+        assert_eq!(covenant_version_problem("Be nice", &newer), None);
+    }
+}
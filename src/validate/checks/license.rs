@@ -0,0 +1,321 @@
+//! Projects must be dual licensed MIT OR Apache-2.0 like the rest of our open
+//! source, with the unmodified license texts, the license set in the
+//! `Cargo.toml` of every crate, and the standard license section in the
+//! README.
+
+use super::{Check, Requirement, Subject};
+use crate::{policy::CheckId, validate::severity::Severity};
+use eyre::{eyre, WrapErr};
+use futures::future::{FutureExt, LocalBoxFuture};
+use itertools::Itertools;
+use regex::Regex;
+
+/// The license every crate must have in its `Cargo.toml`.
+const LICENSE: &str = "MIT OR Apache-2.0";
+
+/// The standard license texts, which are kept alongside the tool.
+const LICENSE_FILES: [(&str, &str); 2] = [
+    ("LICENSE-MIT", include_str!("../../../LICENSE-MIT")),
+    ("LICENSE-APACHE", include_str!("../../../LICENSE-APACHE")),
+];
+
+/// The lines of the standard license section that every README must have.
+const README_SECTION: [&str; 4] = [
+    "Licensed under either of",
+    "Apache License, Version 2.0, ([LICENSE-APACHE](LICENSE-APACHE) or \
+<http://www.apache.org/licenses/LICENSE-2.0>)",
+    "MIT license ([LICENSE-MIT](LICENSE-MIT) or <http://opensource.org/licenses/MIT>)",
+    "Unless you explicitly state otherwise, any contribution intentionally submitted for \
+inclusion in the work by you, as defined in the Apache-2.0 license, shall be dual licensed \
+as above, without any additional terms or conditions.",
+];
+
+#[derive(Debug)]
+pub struct License;
+
+impl Check for License {
+    fn id(&self) -> CheckId {
+        CheckId::License
+    }
+
+    fn description(&self) -> &'static str {
+        "Projects must be dual licensed MIT OR Apache-2.0 with the standard license files, \
+Cargo.toml license and README section"
+    }
+
+    /// Many existing projects predate the standard license files, so they
+    /// don't fail validation by default while they are brought in line.
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn requires(&self) -> &'static [Requirement] {
+        &[Requirement::DefaultBranch]
+    }
+
+    fn run<'a>(&'a self, subject: &'a Subject<'a>) -> LocalBoxFuture<'a, eyre::Result<()>> {
+        async move {
+            let mut problems = Vec::new();
+            for (path, standard) in &LICENSE_FILES {
+                match download(subject, path).await? {
+                    Some(text) if is_same_license(&text, standard) => (),
+                    Some(_) => problems.push(format!("{} differs from the standard text", path)),
+                    None => problems.push(format!("{} is missing", path)),
+                }
+            }
+            problems.extend(cargo_manifest_problems(subject).await?);
+            match download(subject, "README.md").await? {
+                Some(readme) => problems.extend(readme_problem(&readme)),
+                None => problems.push("README.md is missing".to_string()),
+            }
+
+            if problems.is_empty() {
+                Ok(())
+            } else {
+                Err(eyre!(
+                    "Not dual licensed {}:\n{}",
+                    LICENSE,
+                    problems.join("\n")
+                ))
+            }
+        }
+        .boxed_local()
+    }
+}
+
+async fn download(subject: &Subject<'_>, path: &str) -> eyre::Result<Option<String>> {
+    subject
+        .client
+        .download_optional_repo_file(
+            &subject.repo.organisation,
+            &subject.repo.name,
+            subject.branch(),
+            path,
+        )
+        .await
+        .wrap_err_with(|| format!("Unable to read {}", path))
+}
+
+/// Whether a license is the same as the standard one, ignoring how it is
+/// wrapped and the copyright notice, which names the year and holder.
+fn is_same_license(text: &str, standard: &str) -> bool {
+    let words = |text: &str| -> Vec<String> {
+        text.lines()
+            .filter(|line| !line.trim_start().starts_with("Copyright"))
+            .flat_map(str::split_whitespace)
+            .map(str::to_string)
+            .collect()
+    };
+    words(text) == words(standard)
+}
+
+/// The problems with the license of the crate at the root of the repo, if
+/// there is one, and of every member of its workspace.
+async fn cargo_manifest_problems(subject: &Subject<'_>) -> eyre::Result<Vec<String>> {
+    // Projects without a root manifest aren't Rust crates
+    let root = match download(subject, "Cargo.toml").await? {
+        Some(text) => parse_manifest("Cargo.toml", &text)?,
+        None => return Ok(Vec::new()),
+    };
+    let workspace = root.get("workspace");
+    let workspace_license = workspace
+        .and_then(|workspace| workspace.get("package"))
+        .and_then(|package| package.get("license"))
+        .and_then(toml::Value::as_str);
+    let mut problems: Vec<_> = package_license_problem("Cargo.toml", &root, workspace_license)
+        .into_iter()
+        .collect();
+
+    let paths = |key: &str| -> Vec<String> {
+        workspace
+            .and_then(|workspace| workspace.get(key))
+            .and_then(toml::Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(toml::Value::as_str)
+            .map(|path| path.trim_end_matches('/').to_string())
+            .collect()
+    };
+    let excluded = paths("exclude");
+    let (globs, mut members): (Vec<_>, Vec<_>) = paths("members")
+        .into_iter()
+        .partition(|member| member.contains(&['*', '?'][..]));
+
+    // Expand globbed members to the directories with a manifest they match
+    if !globs.is_empty() {
+        let tree = subject
+            .client
+            .repo_tree(
+                &subject.repo.organisation,
+                &subject.repo.name,
+                subject.branch(),
+            )
+            .await
+            .wrap_err("Unable to find the workspace members")?;
+        let globs: Vec<_> = globs.iter().map(|glob| glob_regex(glob)).collect();
+        members.extend(
+            tree.paths
+                .iter()
+                .filter_map(|path| path.strip_suffix("/Cargo.toml"))
+                .filter(|dir| globs.iter().any(|glob| glob.is_match(dir)))
+                .map(str::to_string),
+        );
+    }
+
+    for member in members
+        .iter()
+        .filter(|member| !excluded.contains(member) && !matches!(member.as_str(), "" | "."))
+        .sorted()
+        .dedup()
+    {
+        let path = format!("{}/Cargo.toml", member);
+        match download(subject, &path).await? {
+            Some(text) => {
+                let manifest = parse_manifest(&path, &text)?;
+                problems.extend(package_license_problem(&path, &manifest, workspace_license));
+            }
+            None => problems.push(format!("{} is missing", path)),
+        }
+    }
+    Ok(problems)
+}
+
+fn parse_manifest(path: &str, text: &str) -> eyre::Result<toml::Value> {
+    toml::from_str(text).wrap_err_with(|| format!("Unable to parse {}", path))
+}
+
+/// What is wrong with the license of the package in a manifest, if it has a
+/// package and something is wrong. Packages may inherit the license of their
+/// workspace.
+fn package_license_problem(
+    path: &str,
+    manifest: &toml::Value,
+    workspace_license: Option<&str>,
+) -> Option<String> {
+    let package = manifest.get("package")?;
+    let license = match package.get("license") {
+        Some(toml::Value::String(license)) => Some(license.as_str()),
+        Some(toml::Value::Table(table))
+            if table.get("workspace") == Some(&toml::Value::Boolean(true)) =>
+        {
+            workspace_license
+        }
+        _ => None,
+    };
+    match license {
+        Some(LICENSE) => None,
+        Some(license) => Some(format!(
+            "{} has `license = \"{}\"` rather than `license = \"{}\"`",
+            path, license, LICENSE
+        )),
+        None => Some(format!(
+            "{} has no license, it should have `license = \"{}\"`",
+            path, LICENSE
+        )),
+    }
+}
+
+/// What is missing from the license section of a README, if anything.
+fn readme_problem(readme: &str) -> Option<String> {
+    let normalise = |text: &str| text.split_whitespace().join(" ");
+    let readme = normalise(readme);
+    let missing = README_SECTION
+        .iter()
+        .filter(|line| !readme.contains(&normalise(line)))
+        .map(|line| format!("  {}", line))
+        .join("\n");
+    if missing.is_empty() {
+        None
+    } else {
+        Some(format!(
+            "README.md is missing these lines of the standard license section:\n{}",
+            missing
+        ))
+    }
+}
+
+/// A regex matching the directories a workspace member glob matches.
+fn glob_regex(glob: &str) -> Regex {
+    let pattern = regex::escape(glob)
+        .replace(r"\*\*", ".*")
+        .replace(r"\*", "[^/]*")
+        .replace(r"\?", "[^/]");
+    Regex::new(&format!("^{}$", pattern)).expect("escaped globs are valid regexes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn license_texts_are_compared_without_copyright_or_wrapping() {
+        let (_, mit) = LICENSE_FILES[0];
+        let rewrapped = mit
+            .replace(
+                "Copyright (c) 2019 Embark Studios",
+                "Copyright (c) 2024 Someone",
+            )
+            .replace("to any\nperson", "to any person");
+        assert!(is_same_license(&rewrapped, mit));
+        assert!(!is_same_license(
+            &mit.replace("free of charge", "for a fee"),
+            mit
+        ));
+        let (_, apache) = LICENSE_FILES[1];
+        assert!(!is_same_license(mit, apache));
+    }
+
+    #[test]
+    fn package_licenses() {
+        let problem = |manifest: &str| {
+            let manifest: toml::Value = toml::from_str(manifest).unwrap();
+            package_license_problem("Cargo.toml", &manifest, Some("MIT"))
+        };
+        assert_eq!(problem("[package]\nlicense = \"MIT OR Apache-2.0\""), None);
+        assert_eq!(
+            problem("[package]\nlicense = \"MIT\""),
+            Some(
+                "Cargo.toml has `license = \"MIT\"` rather than `license = \"MIT OR Apache-2.0\"`"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            problem("[package]\nlicense.workspace = true"),
+            Some(
+                "Cargo.toml has `license = \"MIT\"` rather than `license = \"MIT OR Apache-2.0\"`"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            problem("[package]\nname = \"some-project\""),
+            Some(
+                "Cargo.toml has no license, it should have `license = \"MIT OR Apache-2.0\"`"
+                    .to_string()
+            )
+        );
+        // Virtual manifests have no package to license
+        assert_eq!(problem("[workspace]\nmembers = [\"crates/*\"]"), None);
+    }
+
+    #[test]
+    fn readme_license_section() {
+        let readme = include_str!("../../../README.md");
+        assert_eq!(readme_problem(readme), None);
+        assert_eq!(
+            readme_problem(&readme.replace("Licensed under either of", "Licensed under")),
+            Some(
+                "README.md is missing these lines of the standard license section:\n  \
+Licensed under either of"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn member_globs() {
+        assert!(glob_regex("crates/*").is_match("crates/some-crate"));
+        assert!(!glob_regex("crates/*").is_match("crates/some-crate/examples"));
+        assert!(glob_regex("crates/**").is_match("crates/some-crate/examples"));
+        assert!(!glob_regex("crates/*").is_match("other/some-crate"));
+    }
+}
@@ -0,0 +1,211 @@
+//! Projects must have a maintainer in their organisation, named by the `*`
+//! rule of their CODEOWNERS file.
+
+use super::{Check, Requirement, Subject};
+use crate::{
+    github::{self, CodeOwnersFile, Owner},
+    policy::CheckId,
+    validate::eligibility::Eligibility,
+};
+use eyre::{eyre, WrapErr};
+use futures::future::{FutureExt, LocalBoxFuture};
+use itertools::Itertools;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug)]
+pub struct Maintainers;
+
+impl Check for Maintainers {
+    fn id(&self) -> CheckId {
+        CheckId::Maintainers
+    }
+
+    fn description(&self) -> &'static str {
+        "Projects must have a maintainer who is a public member of their organisation"
+    }
+
+    fn requires(&self) -> &'static [Requirement] {
+        &[Requirement::Codeowners]
+    }
+
+    fn run<'a>(&'a self, subject: &'a Subject<'a>) -> LocalBoxFuture<'a, eyre::Result<()>> {
+        async move {
+            let files = subject.codeowners();
+            let eligibility = subject.owners_eligibility().await?;
+            let maintainers = lookup_maintainers(subject.repo, files, eligibility);
+            // Name the file that was checked as it may not be the one the
+            // maintainers expect
+            match files.first() {
+                Some(file) => maintainers
+                    .map(|_| ())
+                    .wrap_err_with(|| checked_file_description(&file.location, subject.branch())),
+                None => maintainers.map(|_| ()),
+            }
+        }
+        .boxed_local()
+    }
+}
+
+/// The maintainers listed in the CODEOWNERS file that GitHub uses, which is
+/// the first of `files`, if they are all eligible. `eligibility` has every
+/// owner in that file.
+pub fn lookup_maintainers(
+    repo: &github::RepoId,
+    files: &[CodeOwnersFile],
+    eligibility: &HashMap<Owner, Eligibility>,
+) -> eyre::Result<HashSet<Owner>> {
+    let text = match files.first() {
+        Some(file) => &file.text,
+        None => {
+            return Err(eyre!(
+                "No CODEOWNERS file found in repo, checked .github/CODEOWNERS, \
+CODEOWNERS and docs/CODEOWNERS"
+            ))
+            .wrap_err("Unable to determine maintainers")
+        }
+    };
+
+    // Report every invalid line at once rather than only the first
+    let invalid_lines = github::CodeOwners::lint(text)
+        .into_iter()
+        .filter(|diagnostic| diagnostic.level == github::Level::Error)
+        .join("\n");
+    if !invalid_lines.is_empty() {
+        return Err(eyre!("Invalid CODEOWNERS file:\n{}", invalid_lines))
+            .wrap_err("Unable to determine maintainers");
+    }
+
+    // Determine if there is at least 1 primary maintainer listed for each project
+    let maintainers = github::CodeOwners::new(text)
+        .wrap_err("Unable to determine maintainers")?
+        .primary_maintainers()
+        .filter(|maintainers| !maintainers.is_empty())
+        .cloned()
+        .ok_or_else(|| eyre!("No maintainers were found for * the CODEOWNERS file"))?;
+
+    // Ensure all maintainers are in the repo's organisation
+    let mut users_not_in_organisation = Vec::new();
+    let mut users_approved_elsewhere = Vec::new();
+    let mut private_members = Vec::new();
+    let mut missing_accounts = Vec::new();
+    let mut unknown_accounts = Vec::new();
+    let mut renamed_accounts = Vec::new();
+    let mut suspended_accounts = Vec::new();
+    let mut teams_without_members = Vec::new();
+    let mut unknown_teams = Vec::new();
+    let mut unmatched_emails = Vec::new();
+    for maintainer in maintainers.iter().sorted() {
+        match &eligibility[maintainer] {
+            Eligibility::Eligible => (),
+            Eligibility::NotMember => users_not_in_organisation.push(maintainer.to_string()),
+            Eligibility::NotApprovedForRepo(repos) => {
+                users_approved_elsewhere.push(format!("{} ({})", maintainer, repos.join(", ")))
+            }
+            Eligibility::PrivateMember => private_members.push(maintainer.to_string()),
+            Eligibility::AccountMissing => missing_accounts.push(maintainer.to_string()),
+            Eligibility::AccountMissingOrRenamed => unknown_accounts.push(maintainer.to_string()),
+            Eligibility::AccountRenamed(login) => {
+                renamed_accounts.push(format!("@{} with @{}", maintainer, login))
+            }
+            Eligibility::AccountSuspended => suspended_accounts.push(maintainer.to_string()),
+            Eligibility::TeamWithoutMembers => teams_without_members.push(maintainer.to_string()),
+            Eligibility::UnknownTeam => unknown_teams.push(maintainer.to_string()),
+            Eligibility::EmailNotMember(login) => {
+                unmatched_emails.push(format!("{} ({})", maintainer, login))
+            }
+            Eligibility::EmailPrivateMember(login) => {
+                private_members.push(format!("{} ({})", maintainer, login))
+            }
+            Eligibility::EmailWithoutUser => {
+                unmatched_emails.push(format!("{} (no GitHub user)", maintainer))
+            }
+        }
+    }
+
+    let organisation = &repo.organisation;
+    let mut problems = Vec::new();
+    if !users_not_in_organisation.is_empty() {
+        problems.push(format!(
+            "Maintainers not public {} members: {}",
+            organisation,
+            users_not_in_organisation.join(", "),
+        ));
+    }
+    if !users_approved_elsewhere.is_empty() {
+        problems.push(format!(
+            "Maintainers not public {} members, who are only approved to maintain \
+other repos: {}",
+            organisation,
+            users_approved_elsewhere.join(", "),
+        ));
+    }
+    if !private_members.is_empty() {
+        problems.push(format!(
+            "Maintainers who are {} members, but whose membership is private, please \
+make it public: {}",
+            organisation,
+            private_members.join(", "),
+        ));
+    }
+    if !missing_accounts.is_empty() {
+        problems.push(format!(
+            "Maintainer accounts that no longer exist on GitHub, remove them from CODEOWNERS: {}",
+            missing_accounts.join(", "),
+        ));
+    }
+    if !unknown_accounts.is_empty() {
+        problems.push(format!(
+            "Maintainer accounts not found on GitHub, which may have been renamed or deleted, \
+update or remove them in CODEOWNERS: {}",
+            unknown_accounts.join(", "),
+        ));
+    }
+    if !renamed_accounts.is_empty() {
+        problems.push(format!(
+            "Maintainer accounts that have been renamed, replace them in CODEOWNERS: {}",
+            renamed_accounts.join(", "),
+        ));
+    }
+    if !suspended_accounts.is_empty() {
+        problems.push(format!(
+            "Maintainer accounts that are suspended on GitHub, remove them from CODEOWNERS: {}",
+            suspended_accounts.join(", "),
+        ));
+    }
+    if !teams_without_members.is_empty() {
+        problems.push(format!(
+            "Maintainer teams without any public {} members: {}",
+            organisation,
+            teams_without_members.join(", "),
+        ));
+    }
+    if !unmatched_emails.is_empty() {
+        problems.push(format!(
+            "Maintainer emails not belonging to public {} members: {}",
+            organisation,
+            unmatched_emails.join(", "),
+        ));
+    }
+    if !unknown_teams.is_empty() {
+        problems.push(format!(
+            "Maintainer teams not found in the {} organisation: {}",
+            organisation,
+            unknown_teams.join(", "),
+        ));
+    }
+    if !problems.is_empty() {
+        return Err(eyre!(problems.join("\n")));
+    }
+
+    Ok(maintainers)
+}
+
+/// A description of which CODEOWNERS file was checked, mentioning the branch
+/// if it is not one of the conventional ones.
+fn checked_file_description(location: &str, branch: &str) -> String {
+    if github::is_conventional_branch(branch) {
+        format!("Checked {}", location)
+    } else {
+        format!("Checked {} on the default branch `{}`", location, branch)
+    }
+}
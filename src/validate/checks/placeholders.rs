@@ -0,0 +1,246 @@
+//! Projects must resolve the TODOs and replace the placeholders left in the
+//! files they were created with from their template, such as the template's
+//! name, its example badges and its generic README headline.
+
+use super::{Check, Requirement, Subject};
+use crate::{
+    policy::CheckId,
+    validate::{context::Template, severity::Severity},
+};
+use eyre::{eyre, WrapErr};
+use futures::future::{FutureExt, LocalBoxFuture};
+use itertools::Itertools;
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::collections::HashSet;
+
+/// The files that projects customise after creating them from the template.
+const FILES: [&str; 2] = ["README.md", ".github/workflows/rust-ci.yml"];
+
+lazy_static! {
+    /// TODO and FIXME markers, but not the words used in prose.
+    static ref TODO: Regex = Regex::new(r"\b(TODO|FIXME):").unwrap();
+    /// Crates named by the badges and links in a README.
+    static ref CRATE: Regex = Regex::new(
+        r"(?:crates\.io/crates|img\.shields\.io/crates/[a-z]+|docs\.rs|deps\.rs/repo/github/[\w-]+)/([\w-]+)"
+    )
+    .unwrap();
+}
+
+#[derive(Debug)]
+pub struct Placeholders;
+
+impl Check for Placeholders {
+    fn id(&self) -> CheckId {
+        CheckId::Placeholders
+    }
+
+    fn description(&self) -> &'static str {
+        "Projects must resolve the TODOs and placeholders their template left in the README and \
+CI workflow"
+    }
+
+    /// A TODO may be intentional, so they don't fail validation by default.
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn requires(&self) -> &'static [Requirement] {
+        &[Requirement::DefaultBranch]
+    }
+
+    fn run<'a>(&'a self, subject: &'a Subject<'a>) -> LocalBoxFuture<'a, eyre::Result<()>> {
+        async move {
+            let organisation = subject.context.organisation(&subject.repo.organisation)?;
+            let template = match &organisation.template {
+                Some(template) => template,
+                None => return Ok(()),
+            };
+            let placeholders = TemplatePlaceholders::new(template, &subject.repo.name);
+            let mut problems = Vec::new();
+            for path in &FILES {
+                let text = subject
+                    .client
+                    .download_optional_repo_file(
+                        &subject.repo.organisation,
+                        &subject.repo.name,
+                        subject.branch(),
+                        path,
+                    )
+                    .await
+                    .wrap_err_with(|| format!("Unable to read {}", path))?;
+                if let Some(text) = text {
+                    problems.extend(placeholders.find(path, &text));
+                }
+            }
+
+            if problems.is_empty() {
+                Ok(())
+            } else {
+                Err(eyre!(
+                    "Unresolved placeholders from {}:\n{}",
+                    template.repo,
+                    problems.join("\n")
+                ))
+            }
+        }
+        .boxed_local()
+    }
+}
+
+/// What a template leaves in the files of the projects created from it.
+#[derive(Debug)]
+struct TemplatePlaceholders {
+    /// The names the template uses for itself and its example crates, which
+    /// projects should replace with their own
+    names: Vec<(String, Regex)>,
+    /// The title and tagline of the template's README. Its section headings
+    /// are left out, as projects keep standard sections such as the license.
+    headlines: HashSet<String>,
+}
+
+impl TemplatePlaceholders {
+    fn new(template: &Template, project: &str) -> Self {
+        let readme = template.readme.as_deref().unwrap_or_default();
+        let names = std::iter::once(template.repo.name.as_str())
+            .chain(
+                CRATE
+                    .captures_iter(readme)
+                    .filter_map(|captures| Some(captures.get(1)?.as_str())),
+            )
+            // A project may well be named after one of the example crates
+            .filter(|name| !name.eq_ignore_ascii_case(project))
+            .unique()
+            .map(|name| {
+                let word = format!(r"(?:^|[^\w-]){}(?:$|[^\w-])", regex::escape(name));
+                let regex = Regex::new(&word).expect("escaped names are valid regexes");
+                (name.to_string(), regex)
+            })
+            .collect();
+        let headlines = readme
+            .lines()
+            .map(str::trim)
+            .filter(|line| is_headline(line))
+            .map(str::to_string)
+            .collect();
+        Self { names, headlines }
+    }
+
+    /// A line for each placeholder left in a file, naming the file and line.
+    fn find(&self, path: &str, text: &str) -> Vec<String> {
+        text.lines()
+            .enumerate()
+            .filter_map(|(index, line)| {
+                Some(format!("{}:{}: {}", path, index + 1, self.problem(line)?))
+            })
+            .collect()
+    }
+
+    /// The placeholder left in a line, if there is one.
+    fn problem(&self, line: &str) -> Option<String> {
+        if let Some(todo) = TODO.find(line) {
+            // Leave out the end of the HTML comments the README's are in
+            let todo = line[todo.start()..].trim_end_matches(&['-', '>', ' '][..]);
+            return Some(format!("unresolved {}", todo));
+        }
+        if self.headlines.contains(line.trim()) {
+            return Some("headline unchanged from the template".to_string());
+        }
+        let (name, _) = self.names.iter().find(|(_, regex)| regex.is_match(line))?;
+        if line.contains("![") {
+            Some(format!("badge for the template's `{}`", name))
+        } else {
+            Some(format!("placeholder name `{}`", name))
+        }
+    }
+}
+
+/// Whether a line of a README is its title or a tagline in bold.
+fn is_headline(line: &str) -> bool {
+    line.starts_with("# ") || (line.len() > 4 && line.starts_with("**") && line.ends_with("**"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::github;
+
+    const TEMPLATE_README: &str = r#"<!--- FIXME: Pick an emoji and name your project! --->
+# `🌻 opensource-template`
+
+**Template for creating new open source repositories**
+
+[![Embark](https://img.shields.io/badge/embark-open%20source-blueviolet.svg)](https://embark.dev)
+[![Crates.io](https://img.shields.io/crates/v/rust-gpu.svg)](https://crates.io/crates/rust-gpu)
+[![Docs](https://docs.rs/rust-gpu/badge.svg)](https://docs.rs/rust-gpu)
+"#;
+
+    fn placeholders(project: &str) -> TemplatePlaceholders {
+        let template = Template {
+            repo: github::RepoId::new("EmbarkStudios", "opensource-template"),
+            files: Default::default(),
+            readme: Some(TEMPLATE_README.to_string()),
+            max_difference: 0.25,
+        };
+        TemplatePlaceholders::new(&template, project)
+    }
+
+    #[test]
+    fn unchanged_template_readme() {
+        assert_eq!(
+            placeholders("some-project").find("README.md", TEMPLATE_README),
+            vec![
+                "README.md:1: unresolved FIXME: Pick an emoji and name your project!",
+                "README.md:2: headline unchanged from the template",
+                "README.md:4: headline unchanged from the template",
+                "README.md:7: badge for the template's `rust-gpu`",
+                "README.md:8: badge for the template's `rust-gpu`",
+            ]
+        );
+    }
+
+    #[test]
+    fn standard_sections_are_not_placeholders() {
+        let template = format!(
+            "{}\n## TEMPLATE INSTRUCTIONS\n\n## Contributing\n\n\
+We welcome community contributions to this project.\n\n## License\n\n\
+Licensed under either of\n\n### Contribution\n",
+            TEMPLATE_README
+        );
+        let readme =
+            "# `🚀 rust-gpu`\n\n**Making Rust a first-class language for GPU shaders**\n\n\
+## Contributing\n\nWe welcome community contributions to this project.\n\n\
+## License\n\nLicensed under either of\n\n### Contribution\n";
+        let placeholders = TemplatePlaceholders::new(
+            &Template {
+                repo: github::RepoId::new("EmbarkStudios", "opensource-template"),
+                files: Default::default(),
+                readme: Some(template),
+                max_difference: 0.25,
+            },
+            "rust-gpu",
+        );
+        assert_eq!(placeholders.find("README.md", readme), Vec::<String>::new());
+    }
+
+    #[test]
+    fn customised_files() {
+        let readme =
+            "# `🚀 rust-gpu`\n\n**Making Rust a first-class language for GPU shaders**\n\n\
+[![Crates.io](https://img.shields.io/crates/v/rust-gpu.svg)](https://crates.io/crates/rust-gpu)\n\
+See the TODO list in the roadmap.";
+        assert_eq!(
+            placeholders("rust-gpu").find("README.md", readme),
+            Vec::<String>::new()
+        );
+        let workflow = "# TODO: Replace this with your crate\n\
+run: cargo test -p opensource-template\nrun: cargo test -p opensource-template-macros";
+        assert_eq!(
+            placeholders("some-project").find(".github/workflows/rust-ci.yml", workflow),
+            vec![
+                ".github/workflows/rust-ci.yml:1: unresolved TODO: Replace this with your crate",
+                ".github/workflows/rust-ci.yml:2: placeholder name `opensource-template`",
+            ]
+        );
+    }
+}
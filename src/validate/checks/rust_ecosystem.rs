@@ -0,0 +1,48 @@
+//! Rust projects must be included in their organisation's rust-ecosystem
+//! README.
+
+use super::{Check, Subject};
+use crate::{policy::CheckId, validate::repo_config::ProjectType};
+use eyre::eyre;
+use futures::future::{self, FutureExt, LocalBoxFuture};
+
+#[derive(Debug)]
+pub struct RustEcosystem;
+
+impl Check for RustEcosystem {
+    fn id(&self) -> CheckId {
+        CheckId::RustEcosystem
+    }
+
+    fn description(&self) -> &'static str {
+        "Rust projects must be included in the rust-ecosystem README"
+    }
+
+    fn run<'a>(&'a self, subject: &'a Subject<'a>) -> LocalBoxFuture<'a, eyre::Result<()>> {
+        future::ready(check_rust_ecosystem_registration(subject)).boxed_local()
+    }
+}
+
+fn check_rust_ecosystem_registration(subject: &Subject<'_>) -> eyre::Result<()> {
+    let organisation = subject.context.organisation(&subject.repo.organisation)?;
+    let readme = match &organisation.rust_ecosystem_readme {
+        Some(readme) => readme,
+        None => return Ok(()),
+    };
+    // Without a declared type, go by how the website tags the project. Other
+    // types than Rust skip the check.
+    let is_rust = match subject.config.project_type {
+        Some(project_type) => project_type == ProjectType::Rust,
+        None => organisation
+            .website_projects
+            .iter()
+            .flatten()
+            .find(|proj| proj.name == subject.repo.name)
+            .is_some_and(|project| project.tags.contains("rust")),
+    };
+    if is_rust && !readme.contains(&subject.repo.name) {
+        Err(eyre!("Rust project not in the rust-ecosystem README"))
+    } else {
+        Ok(())
+    }
+}
@@ -0,0 +1,70 @@
+//! Everyone named in CODEOWNERS must still be eligible to be a maintainer, so
+//! that people who have left are noticed.
+
+use super::{owner_problem_lines, parse_used_codeowners, Check, Requirement, Subject};
+use crate::policy::CheckId;
+use eyre::eyre;
+use futures::future::{FutureExt, LocalBoxFuture};
+
+#[derive(Debug)]
+pub struct StaleOwners;
+
+impl Check for StaleOwners {
+    fn id(&self) -> CheckId {
+        CheckId::StaleOwners
+    }
+
+    fn description(&self) -> &'static str {
+        "Everyone named in CODEOWNERS must still be eligible to be a maintainer"
+    }
+
+    fn requires(&self) -> &'static [Requirement] {
+        &[Requirement::Codeowners]
+    }
+
+    /// Check the owners of every rule in the CODEOWNERS file GitHub uses.
+    /// The owners of the primary `*` rule are checked by the maintainers
+    /// check so they are skipped here.
+    fn run<'a>(&'a self, subject: &'a Subject<'a>) -> LocalBoxFuture<'a, eyre::Result<()>> {
+        async move {
+            let (file, codeowners) = match parse_used_codeowners(subject.codeowners()) {
+                Some(used) => used,
+                None => return Ok(()),
+            };
+            let primary_line = codeowners
+                .assignments()
+                .iter()
+                .find(|assignment| assignment.file_pattern() == "*")
+                .map(|assignment| assignment.line());
+            let assignments: Vec<_> = codeowners
+                .assignments()
+                .iter()
+                .filter(|assignment| Some(assignment.line()) != primary_line)
+                .collect();
+
+            let problems = subject
+                .owners_eligibility()
+                .await?
+                .iter()
+                .filter_map(|(owner, eligibility)| {
+                    Some((
+                        owner.clone(),
+                        eligibility.problem(&subject.repo.organisation)?,
+                    ))
+                })
+                .collect();
+            let stale_owners = owner_problem_lines(assignments, &problems);
+
+            if stale_owners.is_empty() {
+                Ok(())
+            } else {
+                Err(eyre!(
+                    "Stale owners in {}:\n{}",
+                    file.location,
+                    stale_owners.join("\n")
+                ))
+            }
+        }
+        .boxed_local()
+    }
+}
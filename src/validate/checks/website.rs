@@ -0,0 +1,39 @@
+//! Projects must be included in their organisation's website data.
+//! Experiments that aren't ready to be listed yet skip this check.
+
+use super::{Check, Subject};
+use crate::{github, policy::CheckId, validate::context::Context};
+use eyre::eyre;
+use futures::future::{self, FutureExt, LocalBoxFuture};
+
+#[derive(Debug)]
+pub struct Website;
+
+impl Check for Website {
+    fn id(&self) -> CheckId {
+        CheckId::Website
+    }
+
+    fn description(&self) -> &'static str {
+        "Projects must be included in the opensource-website data.json"
+    }
+
+    fn run<'a>(&'a self, subject: &'a Subject<'a>) -> LocalBoxFuture<'a, eyre::Result<()>> {
+        future::ready(check_website_data_inclusion(subject.context, subject.repo)).boxed_local()
+    }
+}
+
+pub fn check_website_data_inclusion(context: &Context, repo: &github::RepoId) -> eyre::Result<()> {
+    let organisation = context.organisation(&repo.organisation)?;
+    let website_projects = match &organisation.website_projects {
+        Some(website_projects) => website_projects,
+        None => return Ok(()),
+    };
+    if website_projects.iter().any(|proj| proj.name == repo.name) {
+        Ok(())
+    } else {
+        Err(eyre!(
+            "Project not included in opensource-website data.json"
+        ))
+    }
+}
@@ -0,0 +1,128 @@
+//! GitHub silently ignores code owners who can't write to the repo.
+
+use super::{owner_problem_lines, parse_used_codeowners, Check, Requirement, Subject};
+use crate::{
+    github::{self, CodeOwnersFile, Owner},
+    policy::CheckId,
+    validate::{context::Context, eligibility::login_for_email},
+};
+use eyre::{eyre, WrapErr};
+use futures::future::{FutureExt, LocalBoxFuture};
+use itertools::Itertools;
+use std::collections::HashMap;
+
+#[derive(Debug)]
+pub struct WriteAccess;
+
+impl Check for WriteAccess {
+    fn id(&self) -> CheckId {
+        CheckId::WriteAccess
+    }
+
+    fn description(&self) -> &'static str {
+        "Code owners must be able to write to the repo, as GitHub ignores them otherwise"
+    }
+
+    fn requires(&self) -> &'static [Requirement] {
+        &[Requirement::Codeowners, Requirement::Authentication]
+    }
+
+    fn run<'a>(&'a self, subject: &'a Subject<'a>) -> LocalBoxFuture<'a, eyre::Result<()>> {
+        check_write_access(
+            subject.context,
+            subject.client,
+            subject.repo,
+            subject.codeowners(),
+        )
+        .boxed_local()
+    }
+}
+
+/// Check that every owner in the CODEOWNERS file GitHub uses has write access
+/// to the repo. This requires an authenticated client.
+pub async fn check_write_access(
+    context: &Context,
+    client: &github::Client,
+    repo: &github::RepoId,
+    files: &[CodeOwnersFile],
+) -> eyre::Result<()> {
+    let (file, codeowners) = match parse_used_codeowners(files) {
+        Some(used) => used,
+        None => return Ok(()),
+    };
+    let teams = client
+        .repo_team_permissions(&repo.organisation, &repo.name)
+        .await
+        .wrap_err("Unable to check code owners have write access")?;
+
+    let mut problems = HashMap::new();
+    for owner in codeowners
+        .assignments()
+        .iter()
+        .flat_map(|assignment| assignment.owners())
+        .unique()
+    {
+        let problem = write_access_problem(context, client, repo, &teams, owner)
+            .await
+            .wrap_err("Unable to check code owners have write access")?;
+        if let Some(problem) = problem {
+            let _ = problems.insert(owner.clone(), problem);
+        }
+    }
+    let ignored_owners = owner_problem_lines(codeowners.assignments(), &problems);
+
+    if ignored_owners.is_empty() {
+        Ok(())
+    } else {
+        Err(eyre!(
+            "Owners in {} without write access, who GitHub ignores:\n{}",
+            file.location,
+            ignored_owners.join("\n")
+        ))
+    }
+}
+
+/// Why GitHub would ignore a code owner, if it would.
+async fn write_access_problem(
+    context: &Context,
+    client: &github::Client,
+    repo: &github::RepoId,
+    teams: &HashMap<String, String>,
+    owner: &Owner,
+) -> eyre::Result<Option<String>> {
+    let access = |permission: Option<&str>| match permission {
+        Some(permission) if github::is_write_permission(permission) => None,
+        Some("none") => Some("no access".to_string()),
+        Some(permission) => Some(format!("{} access", permission)),
+        None => Some("no such GitHub user".to_string()),
+    };
+    match owner {
+        Owner::Team { organisation, .. }
+            if !organisation.eq_ignore_ascii_case(&repo.organisation) =>
+        {
+            Ok(Some("team from another organisation".to_string()))
+        }
+        Owner::Team { name, .. } => {
+            let permission = teams.get(&name.to_lowercase()).map(String::as_str);
+            Ok(access(permission.or(Some("none"))).map(|access| format!("team has {}", access)))
+        }
+        Owner::User(login) => {
+            let permission = client
+                .collaborator_permission(&repo.organisation, &repo.name, login)
+                .await?;
+            Ok(access(permission.as_deref()))
+        }
+        Owner::Email(email) => {
+            // Emails without a GitHub user are reported by the maintainers
+            // check
+            let login = match login_for_email(context, client, email).await? {
+                Some(login) => login,
+                None => return Ok(None),
+            };
+            let permission = client
+                .collaborator_permission(&repo.organisation, &repo.name, &login)
+                .await?;
+            Ok(access(permission.as_deref()).map(|access| format!("{} has {}", login, access)))
+        }
+    }
+}
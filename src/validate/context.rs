@@ -1,49 +1,146 @@
-use crate::github;
-use eyre::WrapErr;
-use std::collections::{HashMap, HashSet};
+use crate::{
+    github,
+    policy::{self, Policy},
+};
+use eyre::{eyre, WrapErr};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    path::Path,
+};
 
-/// Data about the state of Embark in general, to be used by multiple checks
-/// across all open source projects.
+/// Data about the state of each organisation in general, to be used by
+/// multiple checks across all open source projects.
 /// This is fetched in advance to prevent multiple checks from having to fetch
 /// the same data, which would be wasteful and run the risk of hitting rate
 /// limits.
 #[derive(Debug)]
 pub struct Context {
-    pub embark_github_organisation_members: HashSet<String>,
-    pub embark_github_repos: HashMap<String, github::Repo>,
-    pub rust_ecosystem_readme: String,
-    pub opensource_website_projects: Vec<OpenSourceWebsiteDataProject>,
+    /// The organisations whose projects are validated, keyed by lowercase
+    /// name as GitHub names are case insensitive.
+    pub organisations: HashMap<String, Organisation>,
+    /// GitHub logins for email addresses used as code owners, keyed by the
+    /// lowercase email address. Emails not in this map are looked up using
+    /// the GitHub search API.
+    pub email_logins: HashMap<String, String>,
+    /// Exceptions to our guidelines, which may expire.
+    pub policy: Policy,
+    /// The date used to decide whether policy exceptions have expired.
+    pub today: chrono::NaiveDate,
+}
+
+/// Data about one GitHub organisation.
+#[derive(Debug, Default)]
+pub struct Organisation {
+    /// The name of the organisation as written in the policy file.
+    pub name: String,
+    pub public_members: HashSet<String>,
+    /// Members whose membership is private, so who don't count as
+    /// maintainers until they make it public. This is only known when the
+    /// client is authenticated as a member of the organisation.
+    pub private_members: HashSet<String>,
+    pub repos: HashMap<String, github::Repo>,
+    /// CODEOWNERS files that were fetched in bulk along with the repos, keyed
+    /// by repo name and in the order GitHub looks for them. An empty list
+    /// means the repo has no CODEOWNERS file. Repos that are missing from the
+    /// map have not been prefetched.
+    pub codeowners: HashMap<String, Vec<github::CodeOwnersFile>>,
+    /// The README that Rust projects must be listed in, if the organisation
+    /// has one.
+    pub rust_ecosystem_readme: Option<String>,
+    /// The projects listed on the organisation's website, if it has one.
+    pub website_projects: Option<Vec<OpenSourceWebsiteDataProject>>,
+    /// The community health files of the repo the organisation's projects
+    /// are created from, if it has one.
+    pub template: Option<Template>,
+}
+
+/// The community health files of a template repo, which projects created
+/// from it must keep.
+#[derive(Debug)]
+pub struct Template {
+    pub repo: github::RepoId,
+    /// The text of each file, keyed by its path in the repo.
+    pub files: BTreeMap<String, String>,
+    /// The template's README, which projects replace with their own.
+    pub readme: Option<String>,
+    /// How much a project's copy of a file may differ from the template's,
+    /// as a fraction of their lines.
+    pub max_difference: f64,
 }
 
 impl Context {
-    pub async fn get(github_api_token: Option<String>) -> eyre::Result<Self> {
-        let client = github::Client::new(github_api_token);
-
-        let (
-            opensource_website_projects,
-            embark_github_organisation_members,
-            embark_github_repos,
-            rust_ecosystem_readme,
-        ) = futures::join!(
-            download_projects_list(),
-            client.public_organisation_members("EmbarkStudios"),
-            client.organisation_repos("EmbarkStudios"),
-            github::download_repo_file("EmbarkStudios", "rust-ecosystem", "main", "README.md")
-        );
+    pub async fn get(
+        client: &github::Client,
+        email_mapping: Option<&Path>,
+        policy: Policy,
+    ) -> eyre::Result<Self> {
+        let email_logins = match email_mapping {
+            Some(path) => load_email_mapping(path)?,
+            None => HashMap::new(),
+        };
+
+        let organisations = policy
+            .organisations()
+            .iter()
+            .map(|organisation| download_organisation(client, organisation));
+        let organisations = futures::future::try_join_all(organisations).await?;
+        let organisations = policy
+            .organisations()
+            .iter()
+            .map(|organisation| organisation.name.to_lowercase())
+            .zip(organisations)
+            .collect();
 
         Ok(Self {
-            embark_github_organisation_members: embark_github_organisation_members?,
-            opensource_website_projects: opensource_website_projects?,
-            rust_ecosystem_readme: rust_ecosystem_readme?,
-            embark_github_repos: embark_github_repos?,
+            organisations,
+            email_logins,
+            policy,
+            today: chrono::Utc::now().date_naive(),
         })
     }
 
-    /// Get the names of all projects found on GitHub and in the opensource
-    /// website data.json. We lookup from both as a project may accidentally be
-    /// in one but not the other.
-    pub fn all_projects(&self) -> HashSet<String> {
-        let website_projects = self.opensource_website_projects.iter().map(|proj| {
+    pub fn organisation(&self, name: &str) -> eyre::Result<&Organisation> {
+        self.find_organisation(name)
+            .ok_or_else(|| eyre!("The {} organisation is not listed in the policy file", name))
+    }
+
+    fn find_organisation(&self, name: &str) -> Option<&Organisation> {
+        self.organisations.get(&name.to_lowercase())
+    }
+
+    /// Whether someone is a public member of an organisation, and so can be a
+    /// maintainer of its projects.
+    pub fn is_public_member(&self, organisation: &str, login: &str) -> bool {
+        self.find_organisation(organisation)
+            .is_some_and(|organisation| organisation.public_members.contains(login))
+    }
+
+    /// Whether someone is a member of an organisation who has kept their
+    /// membership private.
+    pub fn is_private_member(&self, organisation: &str, login: &str) -> bool {
+        self.find_organisation(organisation)
+            .is_some_and(|organisation| organisation.private_members.contains(login))
+    }
+
+    /// Get the names of all projects found on GitHub and on the websites of
+    /// every organisation. We lookup from both as a project may accidentally
+    /// be in one but not the other.
+    pub fn all_projects(&self) -> HashSet<github::RepoId> {
+        self.organisations
+            .values()
+            .flat_map(|organisation| {
+                organisation
+                    .all_projects()
+                    .into_iter()
+                    .map(move |project| github::RepoId::new(&organisation.name, &project))
+            })
+            .collect()
+    }
+}
+
+impl Organisation {
+    fn all_projects(&self) -> HashSet<String> {
+        let website_projects = self.website_projects.iter().flatten().map(|proj| {
             proj.repo
                 .as_ref()
                 .and_then(|repo_url| repo_url.rfind('/').map(|i| &repo_url[i + 1..]))
@@ -51,7 +148,7 @@ impl Context {
                 .to_owned()
         });
         let github_projects = self
-            .embark_github_repos
+            .repos
             .values()
             .filter(|project| project.is_public_active_source_project())
             .map(|project| project.name.to_string());
@@ -59,16 +156,182 @@ impl Context {
     }
 }
 
-async fn download_projects_list() -> eyre::Result<Vec<OpenSourceWebsiteDataProject>> {
-    let data = github::download_repo_json_file::<OpenSourceWebsiteData>(
-        "EmbarkStudios",
-        "opensource-website",
-        "main",
-        "static/data/data.json",
-    )
-    .await
-    .wrap_err("Unable to get list of open source Embark projects")?;
-    Ok(data.projects)
+/// Load a TOML file that maps email addresses to GitHub logins, for the
+/// people whose emails can't be found with the GitHub search API.
+///
+/// ```toml
+/// "someone@embark-studios.com" = "someone"
+/// ```
+fn load_email_mapping(path: &Path) -> eyre::Result<HashMap<String, String>> {
+    let text = std::fs::read_to_string(path)
+        .wrap_err_with(|| format!("Unable to read email mapping {}", path.display()))?;
+    let mapping: HashMap<String, String> = toml::from_str(&text)
+        .wrap_err_with(|| format!("Unable to parse email mapping {}", path.display()))?;
+    Ok(mapping
+        .into_iter()
+        .map(|(email, login)| (email.to_lowercase(), login))
+        .collect())
+}
+
+async fn download_organisation(
+    client: &github::Client,
+    organisation: &policy::Organisation,
+) -> eyre::Result<Organisation> {
+    let name = organisation.name.as_str();
+    let (website_projects, public_members, all_members, repos, rust_ecosystem_readme, template) = futures::join!(
+        download_projects_list(client, organisation.website_data.as_ref()),
+        client.public_organisation_members(name),
+        download_all_members(client, name),
+        download_repos(client, name),
+        download_optional_source(client, organisation.rust_ecosystem_readme.as_ref()),
+        download_template(client, organisation.template.as_ref()),
+    );
+
+    let public_members = public_members?;
+    let private_members = all_members.difference(&public_members).cloned().collect();
+    let github::OrganisationRepos { repos, codeowners } = repos?;
+    Ok(Organisation {
+        name: name.to_string(),
+        public_members,
+        private_members,
+        repos,
+        codeowners,
+        rust_ecosystem_readme: rust_ecosystem_readme?,
+        website_projects: website_projects?,
+        template: template?,
+    })
+}
+
+/// Every member of the organisation including the private ones, if we are
+/// authenticated. This is only used to improve error messages so failure is
+/// not fatal.
+async fn download_all_members(client: &github::Client, organisation: &str) -> HashSet<String> {
+    if !client.is_authenticated() {
+        return HashSet::new();
+    }
+    match client.organisation_members(organisation).await {
+        Ok(members) => members,
+        Err(error) => {
+            eprintln!(
+                "Unable to tell private members of {} from non-members: {:?}",
+                organisation, error
+            );
+            HashSet::new()
+        }
+    }
+}
+
+async fn download_repos(
+    client: &github::Client,
+    organisation: &str,
+) -> eyre::Result<github::OrganisationRepos> {
+    if client.uses_graphql() {
+        return client.organisation_repos_graphql(organisation).await;
+    }
+    Ok(github::OrganisationRepos {
+        repos: client.organisation_repos(organisation).await?,
+        codeowners: HashMap::new(),
+    })
+}
+
+async fn download_projects_list(
+    client: &github::Client,
+    file: Option<&policy::RepoFile>,
+) -> eyre::Result<Option<Vec<OpenSourceWebsiteDataProject>>> {
+    let file = match file {
+        Some(file) => file,
+        None => return Ok(None),
+    };
+    let data = client
+        .download_repo_json_file::<OpenSourceWebsiteData>(
+            &file.repo.organisation,
+            &file.repo.name,
+            &file.branch,
+            &file.path,
+        )
+        .await
+        .wrap_err_with(|| {
+            format!(
+                "Unable to get list of open source projects from {}",
+                file.repo
+            )
+        })?;
+    Ok(Some(data.projects))
+}
+
+/// A file that an organisation's projects must be listed in, if it has one.
+async fn download_optional_source(
+    client: &github::Client,
+    file: Option<&policy::RepoFile>,
+) -> eyre::Result<Option<String>> {
+    match file {
+        Some(file) => client
+            .download_repo_file(
+                &file.repo.organisation,
+                &file.repo.name,
+                &file.branch,
+                &file.path,
+            )
+            .await
+            .map(Some),
+        None => Ok(None),
+    }
+}
+
+/// The community health files of a template repo, if the organisation has
+/// one.
+async fn download_template(
+    client: &github::Client,
+    template: Option<&policy::Template>,
+) -> eyre::Result<Option<Template>> {
+    let template = match template {
+        Some(template) => template,
+        None => return Ok(None),
+    };
+    let repo = &template.repo;
+    let tree = client
+        .repo_tree(&repo.organisation, &repo.name, &template.branch)
+        .await
+        .wrap_err_with(|| format!("Unable to list the files of the template {}", repo))?;
+    let downloads = tree
+        .paths
+        .into_iter()
+        .filter(|path| is_community_file(path))
+        .map(|path| async move {
+            let text = client
+                .download_repo_file(&repo.organisation, &repo.name, &template.branch, &path)
+                .await
+                .wrap_err_with(|| format!("Unable to get {} from the template {}", path, repo))?;
+            Ok::<_, eyre::Report>((path, text))
+        });
+    let files = futures::future::try_join_all(downloads).await?;
+    let readme = client
+        .download_optional_repo_file(
+            &repo.organisation,
+            &repo.name,
+            &template.branch,
+            "README.md",
+        )
+        .await
+        .wrap_err_with(|| format!("Unable to get README.md from the template {}", repo))?;
+    Ok(Some(Template {
+        repo: repo.clone(),
+        files: files.into_iter().collect(),
+        readme,
+        max_difference: template.max_difference,
+    }))
+}
+
+/// Whether a file in a template repo is one of the community health files
+/// that projects must keep.
+fn is_community_file(path: &str) -> bool {
+    matches!(
+        path,
+        "CODE_OF_CONDUCT.md"
+            | "CONTRIBUTING.md"
+            | "CHANGELOG.md"
+            | ".github/pull_request_template.md"
+    ) || path.starts_with(".github/ISSUE_TEMPLATE/")
 }
 
 #[derive(Debug, Clone, serde::Deserialize)]
@@ -0,0 +1,319 @@
+use super::{
+    context::Context,
+    eligibility::{owners_eligibility, Eligibility},
+};
+use crate::{github, policy::Policy, CodeownersCoverage, ReportFormat};
+use eyre::{eyre, WrapErr};
+use itertools::Itertools;
+use std::collections::HashMap;
+
+/// How well a repository's files are covered by its CODEOWNERS file.
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub struct Coverage {
+    /// The location of the CODEOWNERS file used
+    pub codeowners: String,
+    /// The number of files in the repository
+    pub files: usize,
+    /// Whether GitHub left out some of the repository's files
+    pub truncated: bool,
+    pub unowned: Vec<UnownedPath>,
+    pub ineligible: Vec<IneligiblePath>,
+    pub unused_rules: Vec<UnusedRule>,
+}
+
+/// A file without any owner.
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub struct UnownedPath {
+    pub path: String,
+    /// The line of the rule that leaves the file without an owner, or `None`
+    /// if no rule matches the file.
+    pub line: Option<usize>,
+}
+
+/// A file whose owners are all ineligible to be maintainers.
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub struct IneligiblePath {
+    pub path: String,
+    pub line: usize,
+    pub owners: Vec<String>,
+}
+
+/// A rule whose pattern doesn't match any file in the repository.
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub struct UnusedRule {
+    pub line: usize,
+    pub pattern: String,
+}
+
+impl Coverage {
+    /// Resolve every path against the CODEOWNERS rules.
+    pub fn new(
+        file: &github::CodeOwnersFile,
+        codeowners: &github::CodeOwners,
+        tree: &github::RepoTree,
+        eligibility: &HashMap<github::Owner, Eligibility>,
+    ) -> Self {
+        let mut unowned = Vec::new();
+        let mut ineligible = Vec::new();
+        for path in &tree.paths {
+            let line = || {
+                codeowners
+                    .assignment_for(path)
+                    .map(|assignment| assignment.line())
+            };
+            let owners = match codeowners.owners_of(path) {
+                Some(owners) if !owners.is_empty() => owners,
+                _ => {
+                    unowned.push(UnownedPath {
+                        path: path.clone(),
+                        line: line(),
+                    });
+                    continue;
+                }
+            };
+            let is_eligible = |owner| eligibility.get(owner).is_some_and(Eligibility::is_eligible);
+            if !owners.iter().any(is_eligible) {
+                ineligible.push(IneligiblePath {
+                    path: path.clone(),
+                    line: line().expect("an owned path has an assignment"),
+                    owners: owners.iter().sorted().map(ToString::to_string).collect(),
+                });
+            }
+        }
+
+        let unused_rules = codeowners
+            .assignments()
+            .iter()
+            .filter(|assignment| !tree.paths.iter().any(|path| assignment.matches(path)))
+            .map(|assignment| UnusedRule {
+                line: assignment.line(),
+                pattern: assignment.file_pattern().to_string(),
+            })
+            .collect();
+
+        Self {
+            codeowners: file.location.clone(),
+            files: tree.paths.len(),
+            truncated: tree.truncated,
+            unowned,
+            ineligible,
+            unused_rules,
+        }
+    }
+
+    fn is_complete(&self) -> bool {
+        self.unowned.is_empty() && self.ineligible.is_empty() && self.unused_rules.is_empty()
+    }
+
+    /// A plain text table with a row for each problem found.
+    pub fn to_table(&self) -> String {
+        let mut rows = vec![[
+            "PROBLEM".to_string(),
+            "PATH".to_string(),
+            "DETAILS".to_string(),
+        ]];
+        rows.extend(self.unowned.iter().map(|unowned| {
+            let details = match unowned.line {
+                Some(line) => format!("rule on line {} has no owners", line),
+                None => "no matching rule".to_string(),
+            };
+            ["unowned".to_string(), unowned.path.clone(), details]
+        }));
+        rows.extend(self.ineligible.iter().map(|ineligible| {
+            [
+                "ineligible owners".to_string(),
+                ineligible.path.clone(),
+                format!(
+                    "{} (line {})",
+                    ineligible.owners.join(", "),
+                    ineligible.line
+                ),
+            ]
+        }));
+        rows.extend(self.unused_rules.iter().map(|rule| {
+            [
+                "unused rule".to_string(),
+                rule.pattern.clone(),
+                format!("line {} matches no file", rule.line),
+            ]
+        }));
+
+        let widths: Vec<usize> = (0..2)
+            .map(|column| {
+                rows.iter()
+                    .map(|row| row[column].chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        let mut table = String::new();
+        if !self.is_complete() {
+            for [problem, path, details] in &rows {
+                table.push_str(&format!(
+                    "{:problem_width$}  {:path_width$}  {}\n",
+                    problem,
+                    path,
+                    details,
+                    problem_width = widths[0],
+                    path_width = widths[1],
+                ));
+            }
+            table.push('\n');
+        }
+        table.push_str(&format!(
+            "{} files checked against {}: {} unowned, {} with only ineligible owners, {} unused rules",
+            self.files,
+            self.codeowners,
+            self.unowned.len(),
+            self.ineligible.len(),
+            self.unused_rules.len(),
+        ));
+        table
+    }
+}
+
+/// Report which files in a repository from one of our GitHub organisations
+/// are not owned by an eligible maintainer, and which CODEOWNERS
+/// rules match no file.
+pub(crate) async fn codeowners_coverage(options: CodeownersCoverage) -> eyre::Result<()> {
+    let CodeownersCoverage {
+        repo,
+        format,
+        email_mapping,
+        policy,
+        github,
+    } = options;
+    let policy = Policy::load(&policy)?;
+    let client = github::Client::new(github)?;
+    let context = Context::get(&client, email_mapping.as_deref(), policy).await?;
+
+    let coverage = lookup_coverage(&context, &client, &repo).await?;
+    match format {
+        ReportFormat::Table => println!("{}", coverage.to_table()),
+        ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&coverage)?),
+    }
+    Ok(())
+}
+
+/// How well the CODEOWNERS file on a repository's default branch covers its
+/// files.
+pub async fn lookup_coverage(
+    context: &Context,
+    client: &github::Client,
+    repo: &github::RepoId,
+) -> eyre::Result<Coverage> {
+    let _ = context.organisation(&repo.organisation)?;
+
+    let branch = client
+        .repo(&repo.organisation, &repo.name)
+        .await?
+        .default_branch;
+    let (files, tree) = futures::join!(
+        client.download_codeowners_files(&repo.organisation, &repo.name, &branch),
+        client.repo_tree(&repo.organisation, &repo.name, &branch),
+    );
+    let file = files?
+        .into_iter()
+        .next()
+        .ok_or_else(|| eyre!("No CODEOWNERS file found in {}", repo))?;
+    let tree = tree?;
+    if tree.truncated {
+        eprintln!(
+            "warning: {} has too many files for GitHub to list them all",
+            repo
+        );
+    }
+    let codeowners = github::CodeOwners::new(&file.text)
+        .wrap_err_with(|| format!("Unable to parse {}", file.location))?;
+
+    let owners = codeowners
+        .assignments()
+        .iter()
+        .flat_map(|assignment| assignment.owners());
+    let eligibility = owners_eligibility(context, client, repo, owners).await?;
+
+    Ok(Coverage::new(&file, &codeowners, &tree, &eligibility))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use github::Owner;
+
+    fn make_coverage(text: &str, paths: &[&str]) -> Coverage {
+        let file = github::CodeOwnersFile::new(".github/CODEOWNERS", text.to_string());
+        let codeowners = github::CodeOwners::new(text).unwrap();
+        let tree = github::RepoTree {
+            paths: paths.iter().map(ToString::to_string).collect(),
+            truncated: false,
+        };
+        let eligibility = vec![
+            (Owner::User("lpil".to_string()), Eligibility::Eligible),
+            (
+                Owner::User("someone-else".to_string()),
+                Eligibility::NotMember,
+            ),
+        ]
+        .into_iter()
+        .collect();
+        Coverage::new(&file, &codeowners, &tree, &eligibility)
+    }
+
+    #[test]
+    fn fully_covered() {
+        let coverage = make_coverage("* @lpil\ndocs/ @someone-else @lpil", &["a.rs", "docs/b.md"]);
+        assert!(coverage.is_complete());
+        assert_eq!(
+            coverage.to_table(),
+            "2 files checked against .github/CODEOWNERS: 0 unowned, \
+0 with only ineligible owners, 0 unused rules"
+        );
+    }
+
+    #[test]
+    fn problems_are_reported() {
+        let coverage = make_coverage(
+            "src/ @lpil\n# Docs\ndocs/ @someone-else\nold/ @lpil\nvendor/",
+            &["README.md", "src/main.rs", "docs/guide.md", "vendor/lib.c"],
+        );
+        assert_eq!(
+            coverage.unowned,
+            vec![
+                UnownedPath {
+                    path: "README.md".to_string(),
+                    line: None
+                },
+                UnownedPath {
+                    path: "vendor/lib.c".to_string(),
+                    line: Some(5)
+                },
+            ]
+        );
+        assert_eq!(
+            coverage.ineligible,
+            vec![IneligiblePath {
+                path: "docs/guide.md".to_string(),
+                line: 3,
+                owners: vec!["someone-else".to_string()],
+            }]
+        );
+        assert_eq!(
+            coverage.unused_rules,
+            vec![UnusedRule {
+                line: 4,
+                pattern: "old/".to_string()
+            }]
+        );
+        assert_eq!(
+            coverage.to_table(),
+            "\
+PROBLEM            PATH           DETAILS
+unowned            README.md      no matching rule
+unowned            vendor/lib.c   rule on line 5 has no owners
+ineligible owners  docs/guide.md  someone-else (line 3)
+unused rule        old/           line 4 matches no file
+
+4 files checked against .github/CODEOWNERS: 2 unowned, 1 with only ineligible owners, 1 unused rules"
+        );
+    }
+}
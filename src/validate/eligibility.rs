@@ -0,0 +1,195 @@
+use super::context::Context;
+use crate::github::{self, Owner};
+use eyre::WrapErr;
+use std::collections::HashMap;
+
+/// Whether a code owner counts as a maintainer, and if not why not.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Eligibility {
+    Eligible,
+    /// A user that is not a public member of the organisation
+    NotMember,
+    /// A user that is not a member of the organisation, who is allowed to
+    /// maintain other repos
+    NotApprovedForRepo(Vec<String>),
+    /// A user that is a member of the organisation, but not publicly
+    PrivateMember,
+    /// A user account that no longer exists
+    AccountMissing,
+    /// A user login that no account has, which may have been deleted or
+    /// renamed
+    AccountMissingOrRenamed,
+    /// A user account that has been renamed to a new login
+    AccountRenamed(String),
+    /// A user account that has been suspended
+    AccountSuspended,
+    /// A team without any public members of the organisation
+    TeamWithoutMembers,
+    /// A team that is not in the organisation, or does not exist
+    UnknownTeam,
+    /// An email belonging to a GitHub user that is not a public member of the
+    /// organisation
+    EmailNotMember(String),
+    /// An email belonging to a GitHub user that is a member of the
+    /// organisation, but not publicly
+    EmailPrivateMember(String),
+    /// An email that does not belong to any GitHub user
+    EmailWithoutUser,
+}
+
+impl Eligibility {
+    pub fn is_eligible(&self) -> bool {
+        *self == Self::Eligible
+    }
+
+    /// Why an owner is not eligible to maintain a project in an
+    /// organisation, if they are not.
+    pub fn problem(&self, organisation: &str) -> Option<String> {
+        match self {
+            Self::Eligible => None,
+            Self::NotMember => Some(format!("not a public {} member", organisation)),
+            Self::NotApprovedForRepo(repos) => Some(format!(
+                "not a public {} member, only approved to maintain {}",
+                organisation,
+                repos.join(", ")
+            )),
+            Self::PrivateMember => {
+                Some("member, but membership is private, please make it public".to_string())
+            }
+            Self::AccountMissing => {
+                Some("account no longer exists on GitHub, remove it".to_string())
+            }
+            Self::AccountMissingOrRenamed => Some(
+                "account not found on GitHub, it may have been renamed or deleted, update or \
+remove it"
+                    .to_string(),
+            ),
+            Self::AccountRenamed(login) => Some(format!(
+                "account renamed to {login}, replace it with @{login}",
+                login = login
+            )),
+            Self::AccountSuspended => Some("account is suspended on GitHub, remove it".to_string()),
+            Self::TeamWithoutMembers => {
+                Some(format!("team without any public {} members", organisation))
+            }
+            Self::UnknownTeam => Some(format!(
+                "team not found in the {} organisation",
+                organisation
+            )),
+            Self::EmailNotMember(login) => Some(format!(
+                "belongs to {}, who is not a public {} member",
+                login, organisation
+            )),
+            Self::EmailPrivateMember(login) => Some(format!(
+                "belongs to {}, a member whose membership is private, please make it public",
+                login
+            )),
+            Self::EmailWithoutUser => Some("no GitHub user".to_string()),
+        }
+    }
+}
+
+/// Determine whether an owner counts as a maintainer of a repo in its
+/// organisation. A team is accepted if at least one of its members would be
+/// accepted.
+pub async fn owner_eligibility(
+    context: &Context,
+    client: &github::Client,
+    repo: &github::RepoId,
+    owner: &Owner,
+) -> eyre::Result<Eligibility> {
+    let eligibility = match owner {
+        Owner::User(user_name) if is_eligible_login(context, repo, user_name) => {
+            Eligibility::Eligible
+        }
+        Owner::User(user_name) if context.is_private_member(&repo.organisation, user_name) => {
+            Eligibility::PrivateMember
+        }
+        Owner::User(user_name)
+            if context
+                .policy
+                .maintainer_repos(user_name, context.today)
+                .next()
+                .is_some() =>
+        {
+            let repos = context.policy.maintainer_repos(user_name, context.today);
+            Eligibility::NotApprovedForRepo(repos.map(ToString::to_string).collect())
+        }
+        // Work out why the user isn't a member, as they may have changed
+        // their login rather than left
+        Owner::User(user_name) => match client
+            .account(user_name)
+            .await
+            .wrap_err_with(|| format!("Unable to look up the GitHub account {}", user_name))?
+        {
+            github::Account::Exists => Eligibility::NotMember,
+            github::Account::Missing => Eligibility::AccountMissing,
+            github::Account::MissingOrRenamed => Eligibility::AccountMissingOrRenamed,
+            github::Account::Renamed(login) => Eligibility::AccountRenamed(login),
+            github::Account::Suspended => Eligibility::AccountSuspended,
+        },
+        Owner::Team { organisation, .. }
+            if !organisation.eq_ignore_ascii_case(&repo.organisation) =>
+        {
+            Eligibility::UnknownTeam
+        }
+        Owner::Team { organisation, name } => {
+            match client.team_members(organisation, name).await? {
+                Some(members) if members.iter().any(|m| is_eligible_login(context, repo, m)) => {
+                    Eligibility::Eligible
+                }
+                Some(_) => Eligibility::TeamWithoutMembers,
+                None => Eligibility::UnknownTeam,
+            }
+        }
+        Owner::Email(email) => match login_for_email(context, client, email).await? {
+            Some(login) if is_eligible_login(context, repo, &login) => Eligibility::Eligible,
+            Some(login) if context.is_private_member(&repo.organisation, &login) => {
+                Eligibility::EmailPrivateMember(login)
+            }
+            Some(login) => Eligibility::EmailNotMember(login),
+            None => Eligibility::EmailWithoutUser,
+        },
+    };
+    Ok(eligibility)
+}
+
+/// The eligibility of each of the owners of a repo, looking up each owner
+/// only once.
+pub async fn owners_eligibility<'a>(
+    context: &Context,
+    client: &github::Client,
+    repo: &github::RepoId,
+    owners: impl IntoIterator<Item = &'a Owner>,
+) -> eyre::Result<HashMap<Owner, Eligibility>> {
+    let mut eligibility = HashMap::new();
+    for owner in owners {
+        if !eligibility.contains_key(owner) {
+            let owner_eligibility = owner_eligibility(context, client, repo, owner).await?;
+            let _ = eligibility.insert(owner.clone(), owner_eligibility);
+        }
+    }
+    Ok(eligibility)
+}
+
+/// The GitHub login for an email address, from the email mapping if it has
+/// one, otherwise using the GitHub search API.
+pub async fn login_for_email(
+    context: &Context,
+    client: &github::Client,
+    email: &str,
+) -> eyre::Result<Option<String>> {
+    match context.email_logins.get(&email.to_lowercase()) {
+        Some(login) => Ok(Some(login.clone())),
+        None => client
+            .login_for_email(email)
+            .await
+            .wrap_err_with(|| format!("Unable to find the GitHub user for {}", email)),
+    }
+}
+
+fn is_eligible_login(context: &Context, repo: &github::RepoId, login: &str) -> bool {
+    context.is_public_member(&repo.organisation, login)
+        // non-members that are explicitly allowed to maintain this repo
+        || context.policy.allows_maintainer(login, repo, context.today)
+}
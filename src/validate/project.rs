@@ -1,41 +1,115 @@
-use super::context::Context;
-use crate::github;
+use super::{
+    checks::{self, Subject},
+    context::Context,
+    repo_config::{self, RepoConfig},
+    severity::Severity,
+};
+use crate::{
+    github::{self, CodeOwnersFile, Owner},
+    policy::CheckId,
+};
 use eyre::{eyre, WrapErr};
-use futures::TryFutureExt;
 use itertools::Itertools;
-use std::{collections::HashSet, ops::Not};
+use std::collections::HashSet;
 
 #[derive(Debug)]
 pub struct Project {
-    pub name: String,
-    /// Projects must have a maintainer at Embark
-    pub maintainers: eyre::Result<HashSet<String>>,
-    // Rust based projects must be included in the rust-ecosystem README.
-    rust_ecosystem_registration: eyre::Result<()>,
-    // Projects must be included in the opensource website data.json
-    website_data_inclusion: eyre::Result<()>,
+    pub repo: github::RepoId,
+    /// The branch that files are checked on, if it could be determined
+    pub default_branch: Option<String>,
+    /// The owners of the `*` rule in CODEOWNERS, if it has one
+    pub maintainers: HashSet<Owner>,
+    /// Problems that don't stop the project conforming to our guidelines but
+    /// which should be fixed
+    pub warnings: Vec<String>,
+    /// Checks the repo has opted out of, along with the reason why
+    pub skipped_checks: Vec<(CheckId, String)>,
+    // The repo's own config file must be valid if it has one
+    repo_config: eyre::Result<()>,
+    // The default branch and CODEOWNERS files must be found for the checks
+    // that need them
+    codeowners: eyre::Result<()>,
+    // The result of each check that was run in the order they were run, with
+    // how serious it is if the check failed
+    results: Vec<(Severity, eyre::Result<()>)>,
 }
 
 impl Project {
-    pub fn new(name: String) -> Self {
+    pub fn new(repo: github::RepoId) -> Self {
         Self {
-            name,
-            maintainers: not_yet_checked(),
-            website_data_inclusion: not_yet_checked(),
-            rust_ecosystem_registration: not_yet_checked(),
+            repo,
+            default_branch: None,
+            maintainers: HashSet::new(),
+            warnings: Vec::new(),
+            skipped_checks: Vec::new(),
+            repo_config: not_yet_checked(),
+            codeowners: not_yet_checked(),
+            results: Vec::new(),
         }
     }
 
-    pub async fn validate(self, context: &Context) -> Self {
-        let maintainers = self.lookup_project_maintainers(context).await;
-        let rust_ecosystem_registration = self.check_rust_ecosystem_registration(context);
-        let website_data_inclusion = self.check_website_data_inclusion(context);
+    /// Run every check in the registry that the repo hasn't opted out of.
+    pub async fn validate(self, context: &Context, client: &github::Client) -> Self {
+        let mut warnings = Vec::new();
+        let mut config = RepoConfig::default();
+        let mut repo_config = Ok(());
+        let (default_branch, codeowners) = match self.lookup_default_branch(context, client).await {
+            Ok(branch) => {
+                // An invalid config opts out of nothing, so every check runs
+                match self.lookup_repo_config(context, client, &branch).await {
+                    Ok(found) => config = found,
+                    Err(error) => repo_config = Err(error),
+                }
+                let codeowners = self.lookup_codeowners(context, client, &branch).await;
+                (Some(branch), codeowners)
+            }
+            Err(error) => (None, Err(error.wrap_err("Unable to determine maintainers"))),
+        };
+        let (files, codeowners) = match codeowners {
+            Ok(files) => (Some(files), Ok(())),
+            Err(error) => (None, Err(error)),
+        };
+        if let Some(files) = &files {
+            warnings.extend(github::ignored_codeowners_warnings(files));
+        }
+        let maintainers = files
+            .as_deref()
+            .and_then(checks::parse_used_codeowners)
+            .and_then(|(_, codeowners)| codeowners.primary_maintainers().cloned())
+            .unwrap_or_default();
+
+        let subject = Subject {
+            repo: &self.repo,
+            config: &config,
+            context,
+            client,
+            branch: default_branch.as_deref(),
+            codeowners: files.as_deref(),
+            eligibility: Default::default(),
+        };
+        let mut results = Vec::new();
+        for check in checks::ALL.iter().filter(|check| !config.skips(check.id())) {
+            // Failing to find the default branch or CODEOWNERS files is
+            // already reported, and the lack of authentication is reported
+            // once for the whole run
+            if check
+                .requires()
+                .iter()
+                .all(|requirement| subject.meets(*requirement))
+            {
+                results.push((check.severity(), check.run(&subject).await));
+            }
+        }
 
         Self {
-            name: self.name,
+            repo: self.repo,
+            default_branch,
             maintainers,
-            website_data_inclusion,
-            rust_ecosystem_registration,
+            warnings,
+            skipped_checks: config.skip.into_iter().collect(),
+            repo_config,
+            codeowners,
+            results,
         }
     }
 
@@ -43,21 +117,73 @@ impl Project {
         !self.errors().is_empty()
     }
 
+    /// Whether the project could not be fully validated because GitHub rate
+    /// limited us. In this case the errors say nothing about the project.
+    pub fn is_rate_limited(&self) -> bool {
+        self.reports()
+            .any(|(_, report)| github::is_rate_limited(report))
+    }
+
+    /// Whether the project has any findings at or above the threshold.
+    pub fn fails(&self, threshold: Severity) -> bool {
+        self.worst_severity()
+            .is_some_and(|severity| severity >= threshold)
+    }
+
+    /// The severity of the most serious finding, if there are any.
+    pub fn worst_severity(&self) -> Option<Severity> {
+        if self.has_errors() {
+            return Some(Severity::Error);
+        }
+        self.minor_findings()
+            .into_iter()
+            .map(|(severity, _)| severity)
+            .max()
+    }
+
     pub fn errors(&self) -> Vec<&eyre::Report> {
-        let Self {
-            name: _,
-            maintainers,
-            website_data_inclusion,
-            rust_ecosystem_registration,
-        } = self;
-        vec![
-            maintainers.as_ref().err(),
-            website_data_inclusion.as_ref().err(),
-            rust_ecosystem_registration.as_ref().err(),
-        ]
-        .into_iter()
-        .flatten()
-        .collect()
+        self.reports()
+            .filter(|(severity, _)| *severity == Severity::Error)
+            .map(|(_, report)| report)
+            .collect()
+    }
+
+    /// The findings that are less serious than errors, most serious first.
+    pub fn minor_findings(&self) -> Vec<(Severity, String)> {
+        let failed_checks = self
+            .reports()
+            .filter(|(severity, _)| *severity < Severity::Error)
+            .map(|(severity, report)| {
+                let text = crate::error::cause_string(report.as_ref(), false);
+                (severity, text.trim_end().to_string())
+            });
+        let warnings = self
+            .warnings
+            .iter()
+            .map(|warning| (Severity::Warning, warning.clone()));
+        let skipped_checks = self.skipped_checks.iter().map(|(check, reason)| {
+            let text = format!("Skipped the {} check: {}", check, reason);
+            (Severity::Notice, text)
+        });
+        failed_checks
+            .chain(warnings)
+            .chain(skipped_checks)
+            .sorted_by(|(a, _), (b, _)| b.cmp(a))
+            .collect()
+    }
+
+    /// Every failure, with how serious it is.
+    fn reports(&self) -> impl Iterator<Item = (Severity, &eyre::Report)> {
+        let lookups = std::iter::once(&self.repo_config)
+            .chain(std::iter::once(&self.codeowners))
+            .map(|result| (Severity::Error, result));
+        let results = self
+            .results
+            .iter()
+            .map(|(severity, result)| (*severity, result));
+        lookups
+            .chain(results)
+            .filter_map(|(severity, result)| Some((severity, result.as_ref().err()?)))
     }
 
     pub fn errors_to_string(&self, indent: bool) -> Option<String> {
@@ -73,75 +199,67 @@ impl Project {
         )
     }
 
-    pub async fn lookup_project_maintainers(
+    /// The repo's default branch, which is where all of its files are checked.
+    pub async fn lookup_default_branch(
         &self,
         context: &Context,
-    ) -> eyre::Result<HashSet<String>> {
-        // Download CODEOWNERS from one of the accepted branches
-        let get = |branch| {
-            github::download_repo_file("EmbarkStudios", &self.name, branch, ".github/CODEOWNERS")
-        };
-        let text = get("main")
-            .or_else(|_| get("master"))
-            .await
-            .wrap_err("Unable to determine maintainers")?;
-
-        // Determine if there is at least 1 primary maintainer listed for each project
-        let maintainers = github::CodeOwners::new(&text)
-            .wrap_err("Unable to determine maintainers")?
-            .primary_maintainers()
-            .cloned()
-            .ok_or_else(|| eyre!("No maintainers were found for * the CODEOWNERS file"))?;
-
-        // Ensure all maintainers are in the EmbarkStudios organisation
-        let mut maintainers_not_in_embark = maintainers
-            .difference(&context.embark_github_organisation_members)
-            .filter(|user_name| {
-                // filter out non-embark users that are explicitly allowed to be maintained
-                crate::policy::ALLOWED_NON_EMBARK_MAINTAINERS
-                    .iter()
-                    .any(|a| a == user_name)
-                    .not()
-            })
-            .peekable();
-        if maintainers_not_in_embark.peek().is_some() {
-            return Err(eyre!(
-                "Maintainers not public EmbarkStudios members: {}",
-                maintainers_not_in_embark.join(", "),
-            ));
+        client: &github::Client,
+    ) -> eyre::Result<String> {
+        let organisation = context.organisation(&self.repo.organisation)?;
+        if let Some(repo) = organisation.repos.get(&self.repo.name) {
+            return Ok(repo.default_branch.clone());
         }
-
-        Ok(maintainers)
+        let repo = client
+            .repo(&self.repo.organisation, &self.repo.name)
+            .await
+            .wrap_err("Unable to determine default branch")?;
+        Ok(repo.default_branch)
     }
 
-    pub fn check_rust_ecosystem_registration(&self, context: &Context) -> eyre::Result<()> {
-        let tags = match context
-            .opensource_website_projects
-            .iter()
-            .find(|proj| proj.name == self.name)
-        {
-            Some(project) => &project.tags,
-            None => return Ok(()),
-        };
-        if tags.contains("rust") && !context.rust_ecosystem_readme.contains(&self.name) {
-            Err(eyre!("Rust project not in the rust-ecosystem README"))
-        } else {
-            Ok(())
+    /// The repo's own config file, or the default config if it doesn't have
+    /// one.
+    pub async fn lookup_repo_config(
+        &self,
+        context: &Context,
+        client: &github::Client,
+        branch: &str,
+    ) -> eyre::Result<RepoConfig> {
+        let text = client
+            .download_optional_repo_file(
+                &self.repo.organisation,
+                &self.repo.name,
+                branch,
+                repo_config::LOCATION,
+            )
+            .await
+            .wrap_err_with(|| format!("Unable to read {}", repo_config::LOCATION))?;
+        match text {
+            Some(text) => RepoConfig::parse(&text, &context.policy)
+                .wrap_err_with(|| format!("Invalid {}", repo_config::LOCATION)),
+            None => Ok(RepoConfig::default()),
         }
     }
 
-    pub fn check_website_data_inclusion(&self, context: &Context) -> eyre::Result<()> {
-        if context
-            .opensource_website_projects
-            .iter()
-            .any(|proj| proj.name == self.name)
-        {
-            Ok(())
-        } else {
-            Err(eyre!(
-                "Project not included in opensource-website data.json"
-            ))
+    /// Every CODEOWNERS file in the repo, in the order GitHub looks for them.
+    pub async fn lookup_codeowners(
+        &self,
+        context: &Context,
+        client: &github::Client,
+        branch: &str,
+    ) -> eyre::Result<Vec<CodeOwnersFile>> {
+        // Use the CODEOWNERS files fetched in bulk if we have them, otherwise
+        // download them from the default branch
+        let prefetched = context
+            .organisation(&self.repo.organisation)
+            .ok()
+            .and_then(|organisation| organisation.codeowners.get(&self.repo.name));
+        if let Some(files) = prefetched {
+            return Ok(files.clone());
         }
+        client
+            .download_codeowners_files(&self.repo.organisation, &self.repo.name, branch)
+            .await
+            .wrap_err("Unable to determine maintainers")
     }
 }
 
@@ -0,0 +1,181 @@
+//! The optional `.github/embark-oss.toml` file in which a repo can describe
+//! itself and opt out of checks that don't apply to it.
+//!
+//! ```toml
+//! type = "experiment"
+//!
+//! [skip]
+//! write-access = "Maintained from a fork by an outside collaborator"
+//! ```
+
+use crate::policy::{CheckId, Policy};
+use eyre::eyre;
+use itertools::Itertools;
+use std::{collections::BTreeMap, fmt};
+
+/// Where the file is looked for in each repo.
+pub const LOCATION: &str = ".github/embark-oss.toml";
+
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RepoConfig {
+    /// What kind of project the repo holds, if it says
+    #[serde(rename = "type")]
+    pub project_type: Option<ProjectType>,
+    /// Checks the repo opts out of, along with the reason why, including
+    /// those that don't apply to its type of project
+    #[serde(default)]
+    pub skip: BTreeMap<CheckId, String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ProjectType {
+    /// Must be in the rust-ecosystem README, even if the website doesn't tag
+    /// it as Rust
+    Rust,
+    /// Documentation only, so never needs to be in the rust-ecosystem README
+    /// or dual licensed like code
+    Docs,
+    /// Not ready to be listed on the website or in the rust-ecosystem README
+    Experiment,
+    /// Anything else, which never needs to be in the rust-ecosystem README
+    /// or dual licensed like code
+    Other,
+}
+
+impl ProjectType {
+    /// The checks that don't apply to this type of project.
+    pub fn skipped_checks(self) -> &'static [CheckId] {
+        match self {
+            Self::Rust => &[],
+            Self::Docs | Self::Other => &[CheckId::RustEcosystem, CheckId::License],
+            Self::Experiment => &[CheckId::Website, CheckId::RustEcosystem],
+        }
+    }
+}
+
+impl fmt::Display for ProjectType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Rust => "rust",
+            Self::Docs => "docs",
+            Self::Experiment => "experiment",
+            Self::Other => "other",
+        })
+    }
+}
+
+impl RepoConfig {
+    /// Parse the file, rejecting opt outs that have no reason or that the
+    /// policy doesn't allow. The checks that don't apply to the project's type
+    /// are added to the opt outs.
+    pub fn parse(text: &str, policy: &Policy) -> eyre::Result<Self> {
+        let mut config: Self = toml::from_str(text)?;
+        if let Some(check) = config
+            .skip
+            .iter()
+            .find(|(_, reason)| reason.trim().is_empty())
+            .map(|(check, _)| check)
+        {
+            return Err(eyre!("No reason given for skipping the {} check", check));
+        }
+        let required = config
+            .skip
+            .keys()
+            .filter(|check| !policy.allows_skipping(**check))
+            .join(", ");
+        if !required.is_empty() {
+            return Err(eyre!(
+                "Repos may not skip these checks as our policy requires them: {}",
+                required
+            ));
+        }
+        if let Some(project_type) = config.project_type {
+            let checks = project_type.skipped_checks();
+            let required = checks
+                .iter()
+                .filter(|check| !policy.allows_skipping(**check))
+                .join(", ");
+            if !required.is_empty() {
+                return Err(eyre!(
+                    "Repos of type {} may not skip these checks as our policy requires them: {}",
+                    project_type,
+                    required
+                ));
+            }
+            for check in checks {
+                let _ = config
+                    .skip
+                    .entry(*check)
+                    .or_insert_with(|| format!("Does not apply to {} projects", project_type));
+            }
+        }
+        Ok(config)
+    }
+
+    pub fn skips(&self, check: CheckId) -> bool {
+        self.skip.contains_key(&check)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> Policy {
+        Policy::parse("version = 1\nrequired_checks = [\"maintainers\"]").unwrap()
+    }
+
+    #[test]
+    fn valid_config() {
+        let config = RepoConfig::parse(
+            r#"
+type = "docs"
+
+[skip]
+website = "Not ready yet"
+write-access = "Maintained from a fork"
+"#,
+            &policy(),
+        )
+        .unwrap();
+        assert_eq!(config.project_type, Some(ProjectType::Docs));
+        assert!(config.skips(CheckId::Website));
+        assert_eq!(
+            config.skip[&CheckId::RustEcosystem],
+            "Does not apply to docs projects"
+        );
+        assert!(config.skips(CheckId::License));
+        assert!(config.skips(CheckId::WriteAccess));
+        assert!(!config.skips(CheckId::StaleOwners));
+
+        let config = RepoConfig::parse("", &policy()).unwrap();
+        assert_eq!(config.project_type, None);
+        assert!(config.skip.is_empty());
+    }
+
+    #[test]
+    fn invalid_config() {
+        let error = |text: &str| RepoConfig::parse(text, &policy()).unwrap_err().to_string();
+        assert_eq!(
+            error("[skip]\nwebsite = \"\""),
+            "No reason given for skipping the website check"
+        );
+        assert_eq!(
+            error("[skip]\nmaintainers = \"We don't have any\""),
+            "Repos may not skip these checks as our policy requires them: maintainers"
+        );
+        let website_required =
+            Policy::parse("version = 1\nrequired_checks = [\"website\"]").unwrap();
+        assert_eq!(
+            RepoConfig::parse("type = \"experiment\"", &website_required)
+                .unwrap_err()
+                .to_string(),
+            "Repos of type experiment may not skip these checks as our policy requires them: \
+website"
+        );
+        assert!(RepoConfig::parse("type = \"game\"", &policy()).is_err());
+        assert!(RepoConfig::parse("[skip]\nunknown = \"Because\"", &policy()).is_err());
+    }
+}
@@ -0,0 +1,60 @@
+use std::fmt;
+
+/// How serious a finding is. Only findings at or above the threshold given
+/// with `--fail-on` fail validation and are sent to Slack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// Worth knowing about, such as a check the repo has opted out of
+    Notice,
+    /// Should be fixed, but the project still follows our guidelines
+    Warning,
+    /// The project does not follow our guidelines
+    Error,
+}
+
+impl Severity {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Notice => "notice",
+            Self::Warning => "warning",
+            Self::Error => "error",
+        }
+    }
+
+    /// How findings of this severity are marked when printed.
+    pub fn icon(self) -> &'static str {
+        match self {
+            Self::Notice => "ℹ️",
+            Self::Warning => "⚠️",
+            Self::Error => "❌",
+        }
+    }
+
+    /// How findings of this severity are marked in Slack notifications.
+    pub fn slack_emoji(self) -> &'static str {
+        match self {
+            Self::Notice => ":large_blue_circle:",
+            Self::Warning => ":large_orange_circle:",
+            Self::Error => ":red_circle:",
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl std::str::FromStr for Severity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "error" => Ok(Self::Error),
+            "warning" => Ok(Self::Warning),
+            "notice" => Ok(Self::Notice),
+            _ => Err(format!("Unknown severity `{}`", s)),
+        }
+    }
+}
@@ -1,16 +1,59 @@
 use std::collections::{HashMap, HashSet};
 
 use super::*;
+use crate::policy::{CheckId, Policy};
 
 fn make_context() -> Context {
+    let organisation = Organisation {
+        name: "EmbarkStudios".to_string(),
+        rust_ecosystem_readme: Some("Readme!".to_string()),
+        website_projects: Some(Vec::new()),
+        ..Organisation::default()
+    };
     Context {
-        embark_github_organisation_members: HashSet::new(),
-        embark_github_repos: HashMap::new(),
-        rust_ecosystem_readme: "Readme!".to_string(),
-        opensource_website_projects: Vec::new(),
+        organisations: vec![("embarkstudios".to_string(), organisation)]
+            .into_iter()
+            .collect(),
+        email_logins: HashMap::new(),
+        policy: Policy::default(),
+        today: chrono::Utc::now().date_naive(),
     }
 }
 
+fn embark_repo(name: &str) -> github::RepoId {
+    github::RepoId::new("EmbarkStudios", name)
+}
+
+/// A new directory for a test's files, so that concurrent test runs don't
+/// interfere with each other.
+fn unique_temp_dir(name: &str) -> std::path::PathBuf {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    let directory = std::env::temp_dir().join(format!("{}-{}-{}", name, std::process::id(), nanos));
+    std::fs::create_dir_all(&directory).unwrap();
+    directory
+}
+
+/// The policy file kept alongside the tool.
+fn repo_policy() -> std::path::PathBuf {
+    std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("policy.toml")
+}
+
+/// Look up a project's maintainers from the CODEOWNERS files on a branch.
+async fn lookup_maintainers(
+    project: &Project,
+    context: &Context,
+    client: &github::Client,
+    branch: &str,
+) -> eyre::Result<HashSet<github::Owner>> {
+    let files = project.lookup_codeowners(context, client, branch).await?;
+    let eligibility =
+        checks::used_owners_eligibility(context, client, &project.repo, &files).await?;
+    checks::maintainers::lookup_maintainers(&project.repo, &files, &eligibility)
+}
+
 fn make_website_project(name: &str) -> OpenSourceWebsiteDataProject {
     OpenSourceWebsiteDataProject {
         name: name.to_string(),
@@ -22,22 +65,1508 @@ fn make_website_project(name: &str) -> OpenSourceWebsiteDataProject {
 #[test]
 fn check_website_data_inclusion_ok() {
     let name = "some-project";
-    let project = Project::new(name.to_string());
+    let project = Project::new(embark_repo(name));
     let mut context = make_context();
 
     // OK if the project is in the website data.json
     context
-        .opensource_website_projects
+        .organisations
+        .get_mut("embarkstudios")
+        .unwrap()
+        .website_projects
+        .get_or_insert_with(Vec::new)
         .push(make_website_project(name));
-    assert!(project.check_website_data_inclusion(&context).is_ok())
+    assert!(checks::website::check_website_data_inclusion(&context, &project.repo).is_ok())
 }
 
 #[test]
 fn check_website_data_inclusion_ko() {
     let name = "some-project";
-    let project = Project::new(name.to_string());
+    let project = Project::new(embark_repo(name));
     let context = make_context();
 
     // Error if the project is not in the website data.json
-    assert!(project.check_website_data_inclusion(&context).is_err());
+    assert!(checks::website::check_website_data_inclusion(&context, &project.repo).is_err());
+}
+
+#[test]
+fn organisations_are_found_case_insensitively() {
+    let mut context = make_context();
+    let _ = context
+        .organisations
+        .get_mut("embarkstudios")
+        .unwrap()
+        .public_members
+        .insert("lpil".to_string());
+
+    assert!(context.organisation("embarkstudios").is_ok());
+    assert!(context.is_public_member("EMBARKSTUDIOS", "lpil"));
+    assert_eq!(
+        context.organisation("Elsewhere").unwrap_err().to_string(),
+        "The Elsewhere organisation is not listed in the policy file"
+    );
+}
+
+fn make_github_options(server: &wiremock::MockServer) -> crate::GitHubOptions {
+    crate::GitHubOptions {
+        github_api_token: None,
+        github_api_url: server.uri(),
+        github_raw_url: server.uri(),
+        cache_dir: None,
+        no_cache: true,
+        github_graphql: false,
+        github_app_id: None,
+        github_app_key_path: None,
+        installation_id: None,
+    }
+}
+
+async fn mount_get(server: &wiremock::MockServer, url_path: &str, body: &str) {
+    use wiremock::{matchers, Mock, ResponseTemplate};
+    Mock::given(matchers::method("GET"))
+        .and(matchers::path(url_path))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("etag", r#""v1""#)
+                .set_body_string(body),
+        )
+        .mount(server)
+        .await;
+}
+
+async fn mount_embark_org(server: &wiremock::MockServer, codeowners: &str) {
+    mount_get(
+        server,
+        "/orgs/EmbarkStudios/public_members",
+        r#"[{"login": "lpil", "id": 1}]"#,
+    )
+    .await;
+    // An account that exists but isn't a member
+    mount_get(
+        server,
+        "/users/someone-else",
+        r#"{"login": "someone-else", "id": 2}"#,
+    )
+    .await;
+    mount_get(
+        server,
+        "/orgs/EmbarkStudios/repos",
+        r#"[{"name": "some-project", "archived": false, "private": false, "fork": false, "default_branch": "main"}]"#,
+    )
+    .await;
+    mount_get(
+        server,
+        "/EmbarkStudios/opensource-website/main/static/data/data.json",
+        r#"{"projects": [{"name": "some-project", "tags": ["rust"]}]}"#,
+    )
+    .await;
+    mount_get(
+        server,
+        "/EmbarkStudios/rust-ecosystem/main/README.md",
+        "- some-project",
+    )
+    .await;
+    mount_get(
+        server,
+        "/EmbarkStudios/some-project/main/.github/CODEOWNERS",
+        codeowners,
+    )
+    .await;
+    mount_license_files(server, "EmbarkStudios/some-project/main").await;
+    mount_template(server).await;
+    mount_community_files(server, "EmbarkStudios/some-project/main").await;
+}
+
+/// Mount the standard license files and a README with the standard license
+/// section for a repo, given as `org/repo/branch`.
+async fn mount_license_files(server: &wiremock::MockServer, repo: &str) {
+    let files = [
+        ("LICENSE-MIT", include_str!("../../LICENSE-MIT")),
+        ("LICENSE-APACHE", include_str!("../../LICENSE-APACHE")),
+        ("README.md", include_str!("../../README.md")),
+    ];
+    for (path, text) in &files {
+        mount_get(server, &format!("/{}/{}", repo, path), text).await;
+    }
+}
+
+/// Mount a README for some-project with an unresolved TODO. It must be
+/// mounted before `mount_embark_org` to take precedence over its README.
+async fn mount_readme_with_todo(server: &wiremock::MockServer) {
+    mount_get(
+        server,
+        "/EmbarkStudios/some-project/main/README.md",
+        &format!(
+            "<!-- TODO: Add a demo -->\n{}",
+            include_str!("../../README.md")
+        ),
+    )
+    .await;
+}
+
+/// The community health files of the opensource-template repo.
+const TEMPLATE_FILES: [(&str, &str); 5] = [
+    (
+        "CODE_OF_CONDUCT.md",
+        include_str!("../../CODE_OF_CONDUCT.md"),
+    ),
+    ("CONTRIBUTING.md", include_str!("../../CONTRIBUTING.md")),
+    ("CHANGELOG.md", include_str!("../../CHANGELOG.md")),
+    (
+        ".github/pull_request_template.md",
+        "### Checklist\n\n* [ ] I have read the [Contributor Guide](../../CONTRIBUTING.md)",
+    ),
+    (
+        ".github/ISSUE_TEMPLATE/bug_report.md",
+        "**Describe the bug**\nA clear and concise description of what the bug is.",
+    ),
+];
+
+/// Mount the opensource-template repo that Embark Studios' projects are
+/// created from.
+async fn mount_template(server: &wiremock::MockServer) {
+    mount_get(
+        server,
+        "/repos/EmbarkStudios/opensource-template/git/trees/main",
+        &serde_json::json!({
+            "sha": "abc",
+            "truncated": false,
+            "tree": TEMPLATE_FILES
+                .iter()
+                .map(|(path, _)| serde_json::json!({ "path": path, "type": "blob" }))
+                .chain(std::iter::once(
+                    serde_json::json!({ "path": "src/lib.rs", "type": "blob" })
+                ))
+                .collect::<Vec<_>>(),
+        })
+        .to_string(),
+    )
+    .await;
+    mount_community_files(server, "EmbarkStudios/opensource-template/main").await;
+    mount_get(
+        server,
+        "/EmbarkStudios/opensource-template/main/README.md",
+        "# `🌻 opensource-template`\n\n**Template for creating new open source repositories**",
+    )
+    .await;
+}
+
+/// Mount the template's community health files for a repo, given as
+/// `org/repo/branch`.
+async fn mount_community_files(server: &wiremock::MockServer, repo: &str) {
+    for (path, text) in &TEMPLATE_FILES {
+        mount_get(server, &format!("/{}/{}", repo, path), text).await;
+    }
+}
+
+/// Mount the permissions of some-project's collaborators and teams.
+async fn mount_write_access(
+    server: &wiremock::MockServer,
+    collaborators: &[(&str, &str)],
+    teams: &str,
+) {
+    for (login, permission) in collaborators {
+        mount_get(
+            server,
+            &format!(
+                "/repos/EmbarkStudios/some-project/collaborators/{}/permission",
+                login
+            ),
+            &serde_json::json!({ "permission": permission }).to_string(),
+        )
+        .await;
+    }
+    mount_get(server, "/repos/EmbarkStudios/some-project/teams", teams).await;
+}
+
+#[tokio::test]
+async fn validate_all_against_local_server_ok() {
+    let server = wiremock::MockServer::start().await;
+    mount_embark_org(&server, "* @lpil").await;
+
+    let options = ValidateAll {
+        slack_webhook_url: None,
+        email_mapping: None,
+        policy: repo_policy(),
+        fail_on: Severity::Error,
+        github: make_github_options(&server),
+    };
+    assert!(all(options).await.is_ok());
+}
+
+#[tokio::test]
+async fn validate_all_against_local_server_ko() {
+    let server = wiremock::MockServer::start().await;
+    mount_embark_org(&server, "* @someone-else").await;
+
+    let options = ValidateAll {
+        slack_webhook_url: None,
+        email_mapping: None,
+        policy: repo_policy(),
+        fail_on: Severity::Error,
+        github: make_github_options(&server),
+    };
+    assert!(all(options).await.is_err());
+}
+
+#[tokio::test]
+async fn validate_all_fails_on_findings_at_the_threshold() {
+    let server = wiremock::MockServer::start().await;
+    mount_readme_with_todo(&server).await;
+    mount_embark_org(&server, "* @lpil").await;
+    let options = |fail_on| ValidateAll {
+        slack_webhook_url: None,
+        email_mapping: None,
+        policy: repo_policy(),
+        fail_on,
+        github: make_github_options(&server),
+    };
+
+    // An unresolved TODO is a warning
+    assert!(all(options(Severity::Error)).await.is_ok());
+    assert!(all(options(Severity::Warning)).await.is_err());
+    assert!(all(options(Severity::Notice)).await.is_err());
+}
+
+#[tokio::test]
+async fn findings_are_reported_by_severity() {
+    let server = wiremock::MockServer::start().await;
+    mount_readme_with_todo(&server).await;
+    mount_embark_org(&server, "* @lpil").await;
+    mount_get(
+        &server,
+        "/EmbarkStudios/some-project/main/.github/embark-oss.toml",
+        "[skip]\nstale-owners = \"Docs are written by contractors\"",
+    )
+    .await;
+    let client = github::Client::new(make_github_options(&server)).unwrap();
+    let context = Context::get(&client, None, Policy::default())
+        .await
+        .unwrap();
+    let project = Project::new(embark_repo("some-project"))
+        .validate(&context, &client)
+        .await;
+
+    assert!(!project.has_errors());
+    assert_eq!(project.worst_severity(), Some(Severity::Warning));
+    assert_eq!(
+        project.minor_findings(),
+        vec![
+            (
+                Severity::Warning,
+                "Unresolved placeholders from EmbarkStudios/opensource-template:
+README.md:1: unresolved TODO: Add a demo"
+                    .to_string()
+            ),
+            (
+                Severity::Notice,
+                "Skipped the stale-owners check: Docs are written by contractors".to_string()
+            ),
+        ]
+    );
+    assert!(!project.fails(Severity::Error));
+    assert!(project.fails(Severity::Warning));
+
+    // Slack only gets the findings at or above the threshold
+    let block = |threshold| {
+        slack_project_block(&project, threshold).map(|block| block.into_json().to_string())
+    };
+    assert!(block(Severity::Error).is_none());
+    let warnings = block(Severity::Warning).unwrap();
+    assert!(
+        warnings.contains(":large_orange_circle: *<https://github.com/EmbarkStudios/some-project")
+    );
+    assert!(warnings.contains("unresolved TODO: Add a demo"));
+    assert!(!warnings.contains("Skipped"));
+    let notices = block(Severity::Notice).unwrap();
+    assert!(notices.contains(":large_blue_circle: Skipped the stale-owners check"));
+}
+
+#[tokio::test]
+async fn validate_all_retries_transient_errors() {
+    use wiremock::{matchers, Mock, ResponseTemplate};
+    let server = wiremock::MockServer::start().await;
+    Mock::given(matchers::method("GET"))
+        .and(matchers::path("/orgs/EmbarkStudios/public_members"))
+        .respond_with(ResponseTemplate::new(502))
+        .up_to_n_times(1)
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    mount_embark_org(&server, "* @lpil").await;
+
+    let options = ValidateAll {
+        slack_webhook_url: None,
+        email_mapping: None,
+        policy: repo_policy(),
+        fail_on: Severity::Error,
+        github: make_github_options(&server),
+    };
+    assert!(all(options).await.is_ok());
+}
+
+#[tokio::test]
+async fn validate_all_reports_rate_limiting() {
+    use wiremock::{matchers, Mock, ResponseTemplate};
+    let server = wiremock::MockServer::start().await;
+    Mock::given(matchers::method("GET"))
+        .and(matchers::path_regex("^/EmbarkStudios/some-project/"))
+        .respond_with(ResponseTemplate::new(429).insert_header("retry-after", "3600"))
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    mount_embark_org(&server, "* @lpil").await;
+
+    let options = ValidateAll {
+        slack_webhook_url: None,
+        email_mapping: None,
+        policy: repo_policy(),
+        fail_on: Severity::Error,
+        github: make_github_options(&server),
+    };
+    assert_eq!(
+        all(options).await.unwrap_err().to_string(),
+        "Rate limited by GitHub, unable to validate: EmbarkStudios/some-project"
+    );
+}
+
+#[tokio::test]
+async fn validate_all_uses_cached_responses() {
+    use wiremock::{matchers, Mock, ResponseTemplate};
+    let cache_dir = unique_temp_dir("embark-oss-validate-all-cache-test");
+    let server = wiremock::MockServer::start().await;
+    let options = || {
+        let mut github = make_github_options(&server);
+        github.cache_dir = Some(cache_dir.clone());
+        github.no_cache = false;
+        ValidateAll {
+            slack_webhook_url: None,
+            email_mapping: None,
+            policy: repo_policy(),
+            fail_on: Severity::Error,
+            github,
+        }
+    };
+
+    // The first run populates the cache
+    mount_embark_org(&server, "* @lpil").await;
+    assert!(all(options()).await.is_ok());
+
+    // The second run only gets "not modified" responses so must use the cache
+    server.reset().await;
+    Mock::given(matchers::method("GET"))
+        .and(matchers::header("if-none-match", r#""v1""#))
+        .respond_with(ResponseTemplate::new(304))
+        .mount(&server)
+        .await;
+    assert!(all(options()).await.is_ok());
+
+    std::fs::remove_dir_all(&cache_dir).unwrap();
+}
+
+#[tokio::test]
+async fn context_codeowners_prefetched_with_graphql() {
+    use wiremock::{matchers, Mock, ResponseTemplate};
+    let server = wiremock::MockServer::start().await;
+    mount_embark_org(&server, "* @lpil").await;
+    let pages = [
+        (
+            serde_json::Value::Null,
+            include_str!("../github/fixtures/graphql_repos_page_1.json"),
+        ),
+        (
+            serde_json::json!("Y3Vyc29yOnYyOpHOAAAAAQ=="),
+            include_str!("../github/fixtures/graphql_repos_page_2.json"),
+        ),
+    ];
+    for (cursor, body) in pages.iter() {
+        Mock::given(matchers::method("POST"))
+            .and(matchers::path("/graphql"))
+            .and(matchers::header("authorization", "bearer some-token"))
+            .and(matchers::body_partial_json(
+                serde_json::json!({ "variables": { "cursor": cursor } }),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_string(*body))
+            .expect(1)
+            .mount(&server)
+            .await;
+    }
+
+    mount_get(
+        &server,
+        "/users/Jake-Shadle",
+        r#"{"login": "Jake-Shadle", "id": 3}"#,
+    )
+    .await;
+
+    let mut options = make_github_options(&server);
+    options.github_api_token = Some("some-token".to_string());
+    options.github_graphql = true;
+    let client = github::Client::new(options).unwrap();
+    // kajiya's maintainer is allowed by the policy
+    let policy = Policy::load(&repo_policy()).unwrap();
+    let context = Context::get(&client, None, policy).await.unwrap();
+    assert_eq!(
+        context.organisation("EmbarkStudios").unwrap().repos.len(),
+        5
+    );
+
+    // The raw CODEOWNERS for other repos are not mounted so these can only
+    // succeed or fail because of the prefetched files
+    let project = Project::new(embark_repo("texture-synthesis"));
+    assert_eq!(
+        lookup_maintainers(&project, &context, &client, "main")
+            .await
+            .unwrap_err()
+            .to_string(),
+        "Maintainers not public EmbarkStudios members: Jake-Shadle"
+    );
+    let project = Project::new(embark_repo("kajiya"));
+    assert!(lookup_maintainers(&project, &context, &client, "main")
+        .await
+        .is_ok());
+    // .github/CODEOWNERS is a directory so the file at the root is used
+    let project = Project::new(embark_repo("odd-one"));
+    assert!(lookup_maintainers(&project, &context, &client, "main")
+        .await
+        .is_ok());
+    let project = Project::new(embark_repo("old-thing"));
+    assert_eq!(
+        lookup_maintainers(&project, &context, &client, "main")
+            .await
+            .unwrap_err()
+            .root_cause()
+            .to_string(),
+        "No CODEOWNERS file found in repo, checked .github/CODEOWNERS, CODEOWNERS and docs/CODEOWNERS"
+    );
+}
+
+#[tokio::test]
+async fn codeowners_locations_are_checked_in_order() {
+    let server = wiremock::MockServer::start().await;
+    mount_embark_org(&server, "* @lpil").await;
+    let client = github::Client::new(make_github_options(&server)).unwrap();
+    let context = Context::get(&client, None, Policy::default())
+        .await
+        .unwrap();
+    let project = Project::new(embark_repo("some-project"));
+
+    // The root file takes precedence over the one in docs/, and failures name
+    // the file that was used
+    let branch = "/EmbarkStudios/some-project/root";
+    mount_get(
+        &server,
+        &format!("{}/CODEOWNERS", branch),
+        "* @someone-else",
+    )
+    .await;
+    mount_get(&server, &format!("{}/docs/CODEOWNERS", branch), "* @lpil").await;
+    let error = lookup_maintainers(&project, &context, &client, "root")
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Maintainers not public EmbarkStudios members: someone-else"
+    );
+    let files = project
+        .lookup_codeowners(&context, &client, "root")
+        .await
+        .unwrap();
+    assert_eq!(
+        github::ignored_codeowners_warnings(&files),
+        vec!["docs/CODEOWNERS is ignored by GitHub and disagrees with CODEOWNERS, which takes precedence"]
+    );
+
+    // Validation passes using .github/CODEOWNERS but warns about the
+    // ignored file
+    mount_get(
+        &server,
+        "/EmbarkStudios/some-project/main/docs/CODEOWNERS",
+        "* @someone-else",
+    )
+    .await;
+    let project = project.validate(&context, &client).await;
+    assert!(!project.has_errors());
+    assert_eq!(
+        project.warnings,
+        vec![
+            "docs/CODEOWNERS is ignored by GitHub and disagrees with .github/CODEOWNERS, which takes precedence",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn validate_uses_default_branch() {
+    let server = wiremock::MockServer::start().await;
+    mount_embark_org(&server, "* @lpil").await;
+    mount_get(
+        &server,
+        "/repos/EmbarkStudios/other-project",
+        r#"{"name": "other-project", "archived": false, "private": false, "fork": false, "default_branch": "develop"}"#,
+    )
+    .await;
+    mount_get(
+        &server,
+        "/EmbarkStudios/other-project/develop/.github/CODEOWNERS",
+        "* @someone-else",
+    )
+    .await;
+
+    let client = github::Client::new(make_github_options(&server)).unwrap();
+    let context = Context::get(&client, None, Policy::default())
+        .await
+        .unwrap();
+
+    // Projects on the conventional branches are reported as usual
+    let project = Project::new(embark_repo("some-project"))
+        .validate(&context, &client)
+        .await;
+    assert_eq!(project.default_branch.as_deref(), Some("main"));
+    assert!(!project.has_errors());
+
+    // The report mentions the branch when it is not a conventional one
+    let project = Project::new(embark_repo("other-project"))
+        .validate(&context, &client)
+        .await;
+    assert_eq!(project.default_branch.as_deref(), Some("develop"));
+    let error = project.errors()[0];
+    assert_eq!(
+        error.to_string(),
+        "Checked .github/CODEOWNERS on the default branch `develop`"
+    );
+    assert_eq!(
+        error.root_cause().to_string(),
+        "Maintainers not public EmbarkStudios members: someone-else"
+    );
+}
+
+#[tokio::test]
+async fn validate_all_as_github_app() {
+    use wiremock::{matchers, Mock, ResponseTemplate};
+    let server = wiremock::MockServer::start().await;
+    Mock::given(matchers::method("POST"))
+        .and(matchers::path("/app/installations/42/access_tokens"))
+        .respond_with(ResponseTemplate::new(201).set_body_json(serde_json::json!({
+            "token": "ghs_installation",
+            "expires_at": "2999-01-01T00:00:00Z",
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(matchers::method("GET"))
+        .and(matchers::path("/orgs/EmbarkStudios/public_members"))
+        .and(matchers::header("authorization", "token ghs_installation"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(r#"[{"login": "lpil", "id": 1}]"#))
+        .expect(1)
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    mount_embark_org(&server, "* @lpil").await;
+    mount_write_access(&server, &[("lpil", "write")], "[]").await;
+
+    let mut github = make_github_options(&server);
+    github.github_app_id = Some(1234);
+    github.github_app_key_path = Some(
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("src/github/fixtures/github_app_test_key.pem"),
+    );
+    github.installation_id = Some(42);
+    let options = ValidateAll {
+        slack_webhook_url: None,
+        email_mapping: None,
+        policy: repo_policy(),
+        fail_on: Severity::Error,
+        github,
+    };
+    assert!(all(options).await.is_ok());
+}
+
+#[tokio::test]
+async fn maintainer_teams_are_resolved_to_members() {
+    use wiremock::{matchers, Mock, ResponseTemplate};
+    let server = wiremock::MockServer::start().await;
+    mount_embark_org(&server, "").await;
+    mount_get(
+        &server,
+        "/orgs/EmbarkStudios/teams/good-team/members",
+        r#"[{"login": "someone-else"}, {"login": "lpil"}]"#,
+    )
+    .await;
+    mount_get(
+        &server,
+        "/orgs/EmbarkStudios/teams/empty-team/members",
+        "[]",
+    )
+    .await;
+    let client = github::Client::new(make_github_options(&server)).unwrap();
+    let context = Context::get(&client, None, Policy::default())
+        .await
+        .unwrap();
+    let project = Project::new(embark_repo("some-project"));
+
+    // A team with an eligible member is accepted
+    mount_get(
+        &server,
+        "/EmbarkStudios/some-project/team-ok/.github/CODEOWNERS",
+        "* @EmbarkStudios/good-team",
+    )
+    .await;
+    let maintainers = lookup_maintainers(&project, &context, &client, "team-ok")
+        .await
+        .unwrap();
+    assert_eq!(
+        maintainers.into_iter().collect::<Vec<_>>(),
+        vec![github::Owner::Team {
+            organisation: "EmbarkStudios".to_string(),
+            name: "good-team".to_string()
+        }]
+    );
+
+    // Empty, unknown and other organisation's teams are rejected
+    mount_get(
+        &server,
+        "/EmbarkStudios/some-project/team-ko/.github/CODEOWNERS",
+        "* @EmbarkStudios/empty-team @EmbarkStudios/missing-team @Elsewhere/team @someone-else",
+    )
+    .await;
+    let error = lookup_maintainers(&project, &context, &client, "team-ko")
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Maintainers not public EmbarkStudios members: someone-else
+Maintainer teams without any public EmbarkStudios members: EmbarkStudios/empty-team
+Maintainer teams not found in the EmbarkStudios organisation: Elsewhere/team, EmbarkStudios/missing-team"
+    );
+
+    // Teams that can't be listed, e.g. without the read:org scope, are an
+    // error rather than reported as not found
+    Mock::given(matchers::method("GET"))
+        .and(matchers::path(
+            "/orgs/EmbarkStudios/teams/secret-team/members",
+        ))
+        .respond_with(ResponseTemplate::new(403))
+        .mount(&server)
+        .await;
+    mount_get(
+        &server,
+        "/EmbarkStudios/some-project/team-forbidden/.github/CODEOWNERS",
+        "* @EmbarkStudios/secret-team",
+    )
+    .await;
+    let error = lookup_maintainers(&project, &context, &client, "team-forbidden")
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Unable to get members of team EmbarkStudios/secret-team"
+    );
+}
+
+#[tokio::test]
+async fn maintainer_emails_are_resolved_to_logins() {
+    use wiremock::{matchers, Mock, ResponseTemplate};
+    let server = wiremock::MockServer::start().await;
+    mount_embark_org(&server, "").await;
+    let search = |kind: &str, query: &str, body: &'static str| {
+        Mock::given(matchers::method("GET"))
+            .and(matchers::path(format!("/search/{}", kind)))
+            .and(matchers::query_param("q", query))
+            .respond_with(ResponseTemplate::new(200).set_body_string(body))
+    };
+    search(
+        "users",
+        "lpil@example.com in:email",
+        r#"{"items": [{"login": "lpil"}]}"#,
+    )
+    // Each email is only searched for once
+    .expect(1)
+    .mount(&server)
+    .await;
+    search("users", "old@example.com in:email", r#"{"items": []}"#)
+        .mount(&server)
+        .await;
+    search(
+        "commits",
+        "author-email:old@example.com",
+        r#"{"items": [{"author": null}, {"author": {"login": "someone-else"}}]}"#,
+    )
+    .mount(&server)
+    .await;
+    search("users", "nobody@example.com in:email", r#"{"items": []}"#)
+        .mount(&server)
+        .await;
+    search(
+        "commits",
+        "author-email:nobody@example.com",
+        r#"{"items": []}"#,
+    )
+    .mount(&server)
+    .await;
+
+    let client = github::Client::new(make_github_options(&server)).unwrap();
+    let mut context = Context::get(&client, None, Policy::default())
+        .await
+        .unwrap();
+    let _ = context
+        .email_logins
+        .insert("mapped@example.com".to_string(), "lpil".to_string());
+    let project = Project::new(embark_repo("some-project"));
+
+    mount_get(
+        &server,
+        "/EmbarkStudios/some-project/ok/.github/CODEOWNERS",
+        "* lpil@example.com Mapped@Example.com",
+    )
+    .await;
+    assert!(lookup_maintainers(&project, &context, &client, "ok")
+        .await
+        .is_ok());
+
+    mount_get(
+        &server,
+        "/EmbarkStudios/some-project/ko/.github/CODEOWNERS",
+        "* lpil@example.com old@example.com nobody@example.com",
+    )
+    .await;
+    assert_eq!(
+        lookup_maintainers(&project, &context, &client, "ko")
+            .await
+            .unwrap_err()
+            .to_string(),
+        "Maintainer emails not belonging to public EmbarkStudios members: \
+nobody@example.com (no GitHub user), old@example.com (someone-else)"
+    );
+}
+
+#[tokio::test]
+async fn invalid_codeowners_lines_are_all_reported() {
+    let server = wiremock::MockServer::start().await;
+    mount_embark_org(
+        &server,
+        "* @lpil lpil\n# Docs\ndocs/ @lpil # inline comment",
+    )
+    .await;
+
+    let client = github::Client::new(make_github_options(&server)).unwrap();
+    let context = Context::get(&client, None, Policy::default())
+        .await
+        .unwrap();
+    let project = Project::new(embark_repo("some-project"));
+    let error = lookup_maintainers(&project, &context, &client, "main")
+        .await
+        .unwrap_err();
+    assert_eq!(error.to_string(), "Unable to determine maintainers");
+    assert_eq!(
+        error.root_cause().to_string(),
+        "Invalid CODEOWNERS file:
+1:9: error: Code owner `lpil` is not an @username, @org/team or email address
+3:13: error: Comments must be on their own line, GitHub treats this as an owner"
+    );
+}
+
+#[tokio::test]
+async fn codeowners_coverage_against_local_server() {
+    let server = wiremock::MockServer::start().await;
+    mount_embark_org(&server, "* @lpil\ndocs/ @someone-else\nold/ @lpil\nvendor/").await;
+    mount_get(
+        &server,
+        "/repos/EmbarkStudios/some-project",
+        r#"{"name": "some-project", "archived": false, "private": false, "fork": false, "default_branch": "main"}"#,
+    )
+    .await;
+    mount_get(
+        &server,
+        "/repos/EmbarkStudios/some-project/git/trees/main",
+        r#"{"sha": "abc", "truncated": false, "tree": [
+            {"path": "src", "type": "tree"},
+            {"path": "src/main.rs", "type": "blob"},
+            {"path": "docs", "type": "tree"},
+            {"path": "docs/guide.md", "type": "blob"},
+            {"path": "vendor/lib.rs", "type": "blob"}
+        ]}"#,
+    )
+    .await;
+
+    let client = github::Client::new(make_github_options(&server)).unwrap();
+    let tree = client
+        .repo_tree("EmbarkStudios", "some-project", "main")
+        .await
+        .unwrap();
+    assert_eq!(
+        tree,
+        github::RepoTree {
+            paths: vec![
+                "src/main.rs".to_string(),
+                "docs/guide.md".to_string(),
+                "vendor/lib.rs".to_string()
+            ],
+            truncated: false,
+        }
+    );
+
+    let options = crate::CodeownersCoverage {
+        repo: embark_repo("some-project"),
+        format: crate::ReportFormat::Json,
+        email_mapping: None,
+        policy: repo_policy(),
+        github: make_github_options(&server),
+    };
+    assert!(codeowners_coverage(options).await.is_ok());
+
+    let context = Context::get(&client, None, Policy::default())
+        .await
+        .unwrap();
+    let coverage = coverage::lookup_coverage(&context, &client, &embark_repo("some-project"))
+        .await
+        .unwrap();
+    assert_eq!(
+        coverage,
+        coverage::Coverage {
+            codeowners: ".github/CODEOWNERS".to_string(),
+            files: 3,
+            truncated: false,
+            unowned: vec![coverage::UnownedPath {
+                path: "vendor/lib.rs".to_string(),
+                line: Some(4),
+            }],
+            ineligible: vec![coverage::IneligiblePath {
+                path: "docs/guide.md".to_string(),
+                line: 2,
+                owners: vec!["someone-else".to_string()],
+            }],
+            unused_rules: vec![coverage::UnusedRule {
+                line: 3,
+                pattern: "old/".to_string(),
+            }],
+        }
+    );
+}
+
+#[tokio::test]
+async fn stale_owners_are_reported_for_every_rule() {
+    let server = wiremock::MockServer::start().await;
+    mount_embark_org(
+        &server,
+        "* @lpil\ndocs/ @lpil @someone-else\n# Vendored code\nvendor/ @lpil old@example.com\nsrc/ @lpil",
+    )
+    .await;
+
+    let client = github::Client::new(make_github_options(&server)).unwrap();
+    let mut context = Context::get(&client, None, Policy::default())
+        .await
+        .unwrap();
+    let _ = context
+        .email_logins
+        .insert("old@example.com".to_string(), "left-long-ago".to_string());
+    let project = Project::new(embark_repo("some-project"))
+        .validate(&context, &client)
+        .await;
+
+    // Only the stale owners are reported, the maintainers are fine
+    assert_eq!(
+        project.errors_to_string(false).unwrap(),
+        "Stale owners in .github/CODEOWNERS:
+line 2 `docs/`: someone-else (not a public EmbarkStudios member)
+line 4 `vendor/`: old@example.com (belongs to left-long-ago, who is not a public EmbarkStudios member)
+"
+    );
+}
+
+#[tokio::test]
+async fn owners_are_looked_up_once_per_project() {
+    use wiremock::{matchers, Mock, ResponseTemplate};
+    let server = wiremock::MockServer::start().await;
+    // Both the maintainers and stale-owners checks need to know about @gone
+    Mock::given(matchers::method("GET"))
+        .and(matchers::path("/users/gone"))
+        .respond_with(ResponseTemplate::new(404))
+        .expect(1)
+        .mount(&server)
+        .await;
+    mount_embark_org(
+        &server,
+        "* @lpil @gone
+docs/ @gone",
+    )
+    .await;
+
+    let client = github::Client::new(make_github_options(&server)).unwrap();
+    let context = Context::get(&client, None, Policy::default())
+        .await
+        .unwrap();
+    let project = Project::new(embark_repo("some-project"))
+        .validate(&context, &client)
+        .await;
+    assert_eq!(project.errors().len(), 2);
+}
+
+#[tokio::test]
+async fn owners_without_write_access_are_reported() {
+    let server = wiremock::MockServer::start().await;
+    mount_embark_org(
+        &server,
+        "* @lpil @EmbarkStudios/Readers\ndocs/ @reader @EmbarkStudios/writers reader@example.com\nsrc/ @ghost @Other/team",
+    )
+    .await;
+    mount_write_access(
+        &server,
+        &[("lpil", "admin"), ("reader", "read")],
+        r#"[{"slug": "readers", "permission": "pull"}, {"slug": "writers", "permission": "push"}]"#,
+    )
+    .await;
+
+    let mut options = make_github_options(&server);
+    options.github_api_token = Some("some-token".to_string());
+    let client = github::Client::new(options).unwrap();
+    let mut context = Context::get(&client, None, Policy::default())
+        .await
+        .unwrap();
+    let _ = context
+        .email_logins
+        .insert("reader@example.com".to_string(), "reader".to_string());
+    let project = Project::new(embark_repo("some-project"));
+    let files = project
+        .lookup_codeowners(&context, &client, "main")
+        .await
+        .unwrap();
+
+    let error = checks::write_access::check_write_access(&context, &client, &project.repo, &files)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Owners in .github/CODEOWNERS without write access, who GitHub ignores:
+line 1 `*`: EmbarkStudios/Readers (team has pull access)
+line 2 `docs/`: reader (read access)
+line 2 `docs/`: reader@example.com (reader has read access)
+line 3 `src/`: ghost (no such GitHub user)
+line 3 `src/`: Other/team (team from another organisation)"
+    );
+}
+
+#[tokio::test]
+async fn missing_renamed_and_suspended_accounts_are_reported() {
+    let cache_dir = unique_temp_dir("embark-oss-account-states-test");
+    let server = wiremock::MockServer::start().await;
+    let mut options = make_github_options(&server);
+    options.cache_dir = Some(cache_dir.clone());
+    options.no_cache = false;
+    let client = github::Client::new(options).unwrap();
+
+    // An earlier run saw the ids of the members
+    mount_get(
+        &server,
+        "/orgs/EmbarkStudios/public_members",
+        r#"[{"login": "lpil", "id": 1}, {"login": "Old-Name", "id": 5}, {"login": "taken", "id": 6}]"#,
+    )
+    .await;
+    let _ = client
+        .public_organisation_members("EmbarkStudios")
+        .await
+        .unwrap();
+    server.reset().await;
+
+    mount_embark_org(
+        &server,
+        "* @lpil @old-name @taken @gone @banned @someone-else",
+    )
+    .await;
+    mount_get(&server, "/user/5", r#"{"login": "new-name", "id": 5}"#).await;
+    mount_get(&server, "/users/taken", r#"{"login": "taken", "id": 99}"#).await;
+    mount_get(
+        &server,
+        "/users/banned",
+        r#"{"login": "banned", "id": 7, "suspended_at": "2021-01-01T00:00:00Z"}"#,
+    )
+    .await;
+    let context = Context::get(&client, None, Policy::default())
+        .await
+        .unwrap();
+    let project = Project::new(embark_repo("some-project"));
+    assert_eq!(
+        lookup_maintainers(&project, &context, &client, "main")
+            .await
+            .unwrap_err()
+            .to_string(),
+        "Maintainers not public EmbarkStudios members: someone-else
+Maintainer accounts that no longer exist on GitHub, remove them from CODEOWNERS: taken
+Maintainer accounts not found on GitHub, which may have been renamed or deleted, update or remove them in CODEOWNERS: gone
+Maintainer accounts that have been renamed, replace them in CODEOWNERS: @old-name with @new-name
+Maintainer accounts that are suspended on GitHub, remove them from CODEOWNERS: banned"
+    );
+
+    std::fs::remove_dir_all(&cache_dir).unwrap();
+}
+
+#[tokio::test]
+async fn private_members_are_asked_to_make_membership_public() {
+    use wiremock::{matchers, Mock, ResponseTemplate};
+    let server = wiremock::MockServer::start().await;
+    mount_embark_org(&server, "* @lpil @private-person private@example.com").await;
+    mount_get(
+        &server,
+        "/users/private-person",
+        r#"{"login": "private-person", "id": 4}"#,
+    )
+    .await;
+
+    // Without authentication private members can't be told apart
+    Mock::given(matchers::method("GET"))
+        .and(matchers::path("/orgs/EmbarkStudios/members"))
+        .and(matchers::header_exists("authorization"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(
+            r#"[{"login": "lpil", "id": 1}, {"login": "private-person", "id": 4}]"#,
+        ))
+        .mount(&server)
+        .await;
+    let client = github::Client::new(make_github_options(&server)).unwrap();
+    let mut context = Context::get(&client, None, Policy::default())
+        .await
+        .unwrap();
+    assert!(context
+        .organisation("EmbarkStudios")
+        .unwrap()
+        .private_members
+        .is_empty());
+
+    let mut options = make_github_options(&server);
+    options.github_api_token = Some("some-token".to_string());
+    let client = github::Client::new(options).unwrap();
+    context = Context::get(&client, None, Policy::default())
+        .await
+        .unwrap();
+    let _ = context.email_logins.insert(
+        "private@example.com".to_string(),
+        "private-person".to_string(),
+    );
+    let project = Project::new(embark_repo("some-project"));
+    assert_eq!(
+        lookup_maintainers(&project, &context, &client, "main")
+            .await
+            .unwrap_err()
+            .to_string(),
+        "Maintainers who are EmbarkStudios members, but whose membership is private, \
+please make it public: private-person, private@example.com (private-person)"
+    );
+}
+
+#[tokio::test]
+async fn policy_exceptions_apply_until_they_expire() {
+    let server = wiremock::MockServer::start().await;
+    mount_embark_org(&server, "* @someone-else").await;
+    let policy_dir = unique_temp_dir("embark-oss-policy-expiry-test");
+    let options = |policy: &str| {
+        let path = policy_dir.join("policy.toml");
+        std::fs::write(&path, policy).unwrap();
+        ValidateAll {
+            slack_webhook_url: None,
+            email_mapping: None,
+            policy: path,
+            fail_on: Severity::Error,
+            github: make_github_options(&server),
+        }
+    };
+    let exception = r#"
+version = 1
+[[maintainers]]
+login = "someone-else"
+repos = ["some-project"]
+justification = "Helping out"
+approved_by = "lpil"
+"#;
+
+    assert!(all(options(exception)).await.is_ok());
+
+    let expired = format!("{}expires = 2020-01-01\n", exception);
+    assert_eq!(
+        all(options(&expired)).await.unwrap_err().to_string(),
+        "Not all projects conform to our guidelines"
+    );
+
+    // Expired exceptions fail validation even when nothing depends on them
+    let expired = format!(
+        r#"{}
+[[ignored_projects]]
+project = "old-project"
+justification = "Not ready yet"
+approved_by = "lpil"
+expires = 2020-01-01
+"#,
+        exception
+    );
+    assert_eq!(
+        all(options(&expired)).await.unwrap_err().to_string(),
+        "Some policy exceptions have expired"
+    );
+
+    std::fs::remove_dir_all(&policy_dir).unwrap();
+}
+
+#[tokio::test]
+async fn maintainer_exceptions_only_apply_to_their_repos() {
+    let server = wiremock::MockServer::start().await;
+    mount_embark_org(&server, "* @someone-else").await;
+    let client = github::Client::new(make_github_options(&server)).unwrap();
+    let policy = Policy::parse(
+        r#"
+version = 1
+[[maintainers]]
+login = "someone-else"
+repos = ["other-project", "another-project"]
+justification = "Helping out"
+approved_by = "lpil"
+"#,
+    )
+    .unwrap();
+    let context = Context::get(&client, None, policy).await.unwrap();
+
+    let project = Project::new(embark_repo("some-project"));
+    assert_eq!(
+        lookup_maintainers(&project, &context, &client, "main")
+            .await
+            .unwrap_err()
+            .to_string(),
+        "Maintainers not public EmbarkStudios members, who are only approved to maintain \
+other repos: someone-else (EmbarkStudios/other-project, EmbarkStudios/another-project)"
+    );
+
+    mount_get(
+        &server,
+        "/EmbarkStudios/other-project/main/.github/CODEOWNERS",
+        "* @someone-else",
+    )
+    .await;
+    let project = Project::new(embark_repo("other-project"));
+    assert!(lookup_maintainers(&project, &context, &client, "main")
+        .await
+        .is_ok());
+}
+
+#[tokio::test]
+async fn repo_config_opts_out_of_checks() {
+    let server = wiremock::MockServer::start().await;
+    mount_embark_org(&server, "* @lpil").await;
+    let client = github::Client::new(make_github_options(&server)).unwrap();
+    let policy = Policy::load(&repo_policy()).unwrap();
+    let context = Context::get(&client, None, policy).await.unwrap();
+
+    // Projects that are not on the website, with a stale owner
+    let validate = |name: &'static str, config: Option<&'static str>| {
+        let server = &server;
+        let context = &context;
+        let client = &client;
+        async move {
+            mount_get(
+                server,
+                &format!("/repos/EmbarkStudios/{}", name),
+                &format!(
+                    r#"{{"name": "{}", "archived": false, "private": false, "fork": false, "default_branch": "main"}}"#,
+                    name
+                ),
+            )
+            .await;
+            mount_get(
+                server,
+                &format!("/EmbarkStudios/{}/main/.github/CODEOWNERS", name),
+                "* @lpil\ndocs/ @someone-else",
+            )
+            .await;
+            mount_community_files(server, &format!("EmbarkStudios/{}/main", name)).await;
+            if let Some(config) = config {
+                mount_get(
+                    server,
+                    &format!("/EmbarkStudios/{}/main/.github/embark-oss.toml", name),
+                    config,
+                )
+                .await;
+            }
+            Project::new(embark_repo(name))
+                .validate(context, client)
+                .await
+        }
+    };
+
+    let project = validate("no-config", None).await;
+    assert_eq!(project.errors().len(), 2);
+    assert!(project.skipped_checks.is_empty());
+
+    let project = validate(
+        "experiment",
+        Some("type = \"experiment\"\n[skip]\nstale-owners = \"Docs are written by contractors\""),
+    )
+    .await;
+    assert!(!project.has_errors());
+    // Checks that don't apply to experiments are reported as skipped
+    let skipped = "Does not apply to experiment projects".to_string();
+    assert_eq!(
+        project.skipped_checks,
+        vec![
+            (
+                CheckId::StaleOwners,
+                "Docs are written by contractors".to_string()
+            ),
+            (CheckId::RustEcosystem, skipped.clone()),
+            (CheckId::Website, skipped),
+        ]
+    );
+
+    // Declared Rust projects must be in the rust-ecosystem README
+    let project = validate(
+        "rust-experiment",
+        Some(
+            "type = \"rust\"\n[skip]\nwebsite = \"Not ready yet\"\nstale-owners = \"Contractors\"",
+        ),
+    )
+    .await;
+    assert_eq!(
+        project.errors()[0].to_string(),
+        "Rust project not in the rust-ecosystem README"
+    );
+    assert_eq!(project.errors().len(), 1);
+
+    // The policy doesn't allow skipping the maintainers check, so it runs
+    // along with every other check
+    let project = validate(
+        "no-maintainers",
+        Some("[skip]\nmaintainers = \"Nobody\"\nwebsite = \"Not ready yet\""),
+    )
+    .await;
+    assert!(project.skipped_checks.is_empty());
+    let errors = project.errors();
+    assert_eq!(errors.len(), 3);
+    assert_eq!(
+        errors[0].root_cause().to_string(),
+        "Repos may not skip these checks as our policy requires them: maintainers"
+    );
+    assert!(errors[1].to_string().starts_with("Stale owners"));
+    assert_eq!(
+        errors[2].to_string(),
+        "Project not included in opensource-website data.json"
+    );
+}
+
+#[tokio::test]
+async fn projects_are_validated_in_every_organisation() {
+    let server = wiremock::MockServer::start().await;
+    mount_embark_org(&server, "* @lpil").await;
+    mount_get(
+        &server,
+        "/orgs/OtherOrg/public_members",
+        r#"[{"login": "someone-else", "id": 2}]"#,
+    )
+    .await;
+    mount_get(
+        &server,
+        "/orgs/OtherOrg/repos",
+        r#"[{"name": "other-project", "archived": false, "private": false, "fork": false, "default_branch": "main"}]"#,
+    )
+    .await;
+    let client = github::Client::new(make_github_options(&server)).unwrap();
+    let policy = Policy::parse(
+        r#"
+version = 1
+
+[[organisations]]
+name = "EmbarkStudios"
+website_data = { repo = "opensource-website", path = "static/data/data.json" }
+rust_ecosystem_readme = { repo = "rust-ecosystem", path = "README.md" }
+
+# Without a website or README those checks are skipped
+[[organisations]]
+name = "OtherOrg"
+"#,
+    )
+    .unwrap();
+    let context = Context::get(&client, None, policy).await.unwrap();
+    assert_eq!(
+        context
+            .all_projects()
+            .into_iter()
+            .sorted()
+            .collect::<Vec<_>>(),
+        vec![
+            embark_repo("some-project"),
+            github::RepoId::new("OtherOrg", "other-project")
+        ]
+    );
+
+    // Maintainers must be members of the project's own organisation
+    mount_get(
+        &server,
+        "/EmbarkStudios/other-project/main/.github/CODEOWNERS",
+        "* @someone-else",
+    )
+    .await;
+    mount_get(
+        &server,
+        "/OtherOrg/other-project/main/.github/CODEOWNERS",
+        "* @someone-else",
+    )
+    .await;
+    let project = Project::new(github::RepoId::new("OtherOrg", "other-project"))
+        .validate(&context, &client)
+        .await;
+    assert!(!project.has_errors());
+    let project = Project::new(embark_repo("other-project"));
+    assert_eq!(
+        lookup_maintainers(&project, &context, &client, "main")
+            .await
+            .unwrap_err()
+            .to_string(),
+        "Maintainers not public EmbarkStudios members: someone-else"
+    );
+
+    // Projects are linked to in their own organisation
+    let project = Project::new(github::RepoId::new("OtherOrg", "missing"))
+        .validate(&context, &client)
+        .await;
+    let blocks = slack_notification_blocks(&[project], &[], Severity::Error)
+        .into_iter()
+        .map(|block| block.into_json().to_string())
+        .join("");
+    assert!(blocks.contains("https://github.com/OtherOrg/missing"));
+}
+
+#[tokio::test]
+async fn projects_must_be_dual_licensed() {
+    let server = wiremock::MockServer::start().await;
+    mount_embark_org(&server, "* @lpil").await;
+    let repo = "/EmbarkStudios/workspace-project/main";
+    mount_get(
+        &server,
+        "/repos/EmbarkStudios/workspace-project",
+        r#"{"name": "workspace-project", "archived": false, "private": false, "fork": false, "default_branch": "main"}"#,
+    )
+    .await;
+    mount_get(&server, &format!("{}/.github/CODEOWNERS", repo), "* @lpil").await;
+    mount_get(
+        &server,
+        &format!("{}/LICENSE-MIT", repo),
+        &include_str!("../../LICENSE-MIT").replace("free of charge", "for a fee"),
+    )
+    .await;
+    mount_get(
+        &server,
+        &format!("{}/README.md", repo),
+        "# workspace-project",
+    )
+    .await;
+    mount_get(
+        &server,
+        &format!("{}/Cargo.toml", repo),
+        r#"
+[workspace]
+members = ["crates/*", "tools/cli"]
+exclude = ["crates/vendored"]
+
+[workspace.package]
+license = "MIT"
+"#,
+    )
+    .await;
+    mount_get(
+        &server,
+        "/repos/EmbarkStudios/workspace-project/git/trees/main",
+        r#"{"sha": "abc", "truncated": false, "tree": [
+            {"path": "Cargo.toml", "type": "blob"},
+            {"path": "crates/inherited/Cargo.toml", "type": "blob"},
+            {"path": "crates/licensed/Cargo.toml", "type": "blob"},
+            {"path": "crates/vendored/Cargo.toml", "type": "blob"},
+            {"path": "crates/licensed/examples/Cargo.toml", "type": "blob"}
+        ]}"#,
+    )
+    .await;
+    mount_get(
+        &server,
+        &format!("{}/crates/inherited/Cargo.toml", repo),
+        "[package]\nname = \"inherited\"\nlicense.workspace = true",
+    )
+    .await;
+    mount_get(
+        &server,
+        &format!("{}/crates/licensed/Cargo.toml", repo),
+        "[package]\nname = \"licensed\"\nlicense = \"MIT OR Apache-2.0\"",
+    )
+    .await;
+
+    let client = github::Client::new(make_github_options(&server)).unwrap();
+    let context = Context::get(&client, None, Policy::default())
+        .await
+        .unwrap();
+    let project = Project::new(embark_repo("workspace-project"))
+        .validate(&context, &client)
+        .await;
+    // Licensing problems are warnings while existing projects are brought in
+    // line
+    let (severity, finding) = project
+        .minor_findings()
+        .into_iter()
+        .find(|(_, finding)| finding.starts_with("Not dual licensed"))
+        .unwrap();
+    assert_eq!(severity, Severity::Warning);
+    assert_eq!(
+        finding,
+        "Not dual licensed MIT OR Apache-2.0:
+LICENSE-MIT differs from the standard text
+LICENSE-APACHE is missing
+crates/inherited/Cargo.toml has `license = \"MIT\"` rather than `license = \"MIT OR Apache-2.0\"`
+tools/cli/Cargo.toml is missing
+README.md is missing these lines of the standard license section:
+  Licensed under either of
+  Apache License, Version 2.0, ([LICENSE-APACHE](LICENSE-APACHE) or <http://www.apache.org/licenses/LICENSE-2.0>)
+  MIT license ([LICENSE-MIT](LICENSE-MIT) or <http://opensource.org/licenses/MIT>)
+  Unless you explicitly state otherwise, any contribution intentionally submitted for inclusion in the work by you, as defined in the Apache-2.0 license, shall be dual licensed as above, without any additional terms or conditions."
+    );
+}
+
+#[tokio::test]
+async fn community_files_must_match_the_template() {
+    let server = wiremock::MockServer::start().await;
+    mount_embark_org(&server, "* @lpil").await;
+    let repo = "/EmbarkStudios/community-project/main";
+    mount_get(
+        &server,
+        "/repos/EmbarkStudios/community-project",
+        r#"{"name": "community-project", "archived": false, "private": false, "fork": false, "default_branch": "main"}"#,
+    )
+    .await;
+    mount_get(&server, &format!("{}/.github/CODEOWNERS", repo), "* @lpil").await;
+    let files = [
+        (
+            "CODE_OF_CONDUCT.md",
+            include_str!("../../CODE_OF_CONDUCT.md").replace("version/1/4", "version/1/3"),
+        ),
+        (
+            "CONTRIBUTING.md",
+            "# Contributing\n\nPlease open an issue first.".to_string(),
+        ),
+        (
+            "CHANGELOG.md",
+            "# Changelog\n\n## 1.0.0\n- Our own release".to_string(),
+        ),
+        (
+            ".github/ISSUE_TEMPLATE/bug_report.md",
+            TEMPLATE_FILES[4].1.to_string(),
+        ),
+    ];
+    for (path, text) in &files {
+        mount_get(&server, &format!("{}/{}", repo, path), text).await;
+    }
+
+    let client = github::Client::new(make_github_options(&server)).unwrap();
+    let context = Context::get(&client, None, Policy::default())
+        .await
+        .unwrap();
+    let project = Project::new(embark_repo("community-project"))
+        .validate(&context, &client)
+        .await;
+    let error = project.errors().into_iter().last().unwrap();
+    assert_eq!(
+        error.to_string(),
+        "Community health files missing or changed from EmbarkStudios/opensource-template:
+.github/pull_request_template.md is missing
+CODE_OF_CONDUCT.md uses version 1.3 of the Contributor Covenant rather than 1.4
+CONTRIBUTING.md differs from the template by 100%, more than the 25% allowed"
+    );
 }